@@ -0,0 +1,181 @@
+//! Custom derives for the Holochain Development Kit
+//!
+//! `#[derive(Validate)]` implements `hdk::Validate` for an entry struct from
+//! `#[validate(...)]` attributes on its fields:
+//! - `non_empty` the string or list must not be empty
+//! - `length(min = 1, max = 280)` bounds on the length of a string (in chars) or list
+//! - `range(min = -10, max = 150)` bounds on a numeric value
+//! - `regex = "^[a-z]+$"` the string must match the regular expression,
+//!   which needs the `regex` feature of the hdk. Invalid patterns, or regex rules
+//!   without that feature, fail the build.
+
+extern crate proc_macro;
+extern crate proc_macro2;
+#[macro_use]
+extern crate quote;
+extern crate regex;
+#[macro_use]
+extern crate syn;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use syn::parse::{Parse, ParseStream, Result as ParseResult};
+use syn::punctuated::Punctuated;
+use syn::{Data, DeriveInput, Fields, Ident, Lit};
+
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn derive_validate(input: TokenStream) -> TokenStream {
+    let input: DeriveInput = syn::parse(input).expect("could not parse derive input");
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => panic!("#[derive(Validate)] only supports structs with named fields"),
+        },
+        _ => panic!("#[derive(Validate)] only supports structs"),
+    };
+
+    let mut checks = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let field_name = ident.to_string();
+        for attr in &field.attrs {
+            if !attr.path.is_ident("validate") {
+                continue;
+            }
+            let rules: Rules = syn::parse2(attr.tts.clone()).unwrap_or_else(|err| {
+                panic!("invalid #[validate(...)] on field `{}`: {}", field_name, err)
+            });
+            for rule in rules.0 {
+                checks.push(field_check(ident, &field_name, &rule));
+            }
+        }
+    }
+
+    let expanded = quote! {
+        impl #impl_generics ::hdk::Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> Result<(), Vec<::hdk::ValidationError>> {
+                let mut errors = Vec::new();
+                #(
+                    if let Err(error) = #checks {
+                        errors.push(error);
+                    }
+                )*
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(errors)
+                }
+            }
+        }
+    };
+    expanded.into()
+}
+
+/// The rules of a `#[validate(...)]` attribute
+struct Rules(Punctuated<Rule, Token![,]>);
+
+/// A single rule: `non_empty`, `regex = ".."`, `length(..)` or `range(..)`
+enum Rule {
+    Word(Ident),
+    NameValue(Ident, Lit),
+    List(Ident, Punctuated<Bound, Token![,]>),
+}
+
+/// A `min = ..` or `max = ..` argument of a rule. Unlike in `syn::Meta`,
+/// the literal may be negative.
+struct Bound {
+    name: Ident,
+    value: TokenStream2,
+}
+
+impl Parse for Rules {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let content;
+        parenthesized!(content in input);
+        Ok(Rules(content.parse_terminated(Rule::parse)?))
+    }
+}
+
+impl Parse for Rule {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let name: Ident = input.parse()?;
+        if input.peek(Token![=]) {
+            input.parse::<Token![=]>()?;
+            Ok(Rule::NameValue(name, input.parse()?))
+        } else if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Ok(Rule::List(name, content.parse_terminated(Bound::parse)?))
+        } else {
+            Ok(Rule::Word(name))
+        }
+    }
+}
+
+impl Parse for Bound {
+    fn parse(input: ParseStream) -> ParseResult<Self> {
+        let name: Ident = input.parse()?;
+        input.parse::<Token![=]>()?;
+        let minus: Option<Token![-]> = input.parse()?;
+        let lit: Lit = input.parse()?;
+        Ok(Bound {
+            name,
+            value: quote!(#minus #lit),
+        })
+    }
+}
+
+/// Build the call to the `hdk::validation` check for a single rule
+fn field_check(ident: &Ident, field_name: &str, rule: &Rule) -> TokenStream2 {
+    match *rule {
+        Rule::Word(ref word) if word == "non_empty" => quote! {
+            ::hdk::validation::validate_non_empty(&self.#ident, #field_name)
+        },
+        Rule::NameValue(ref name, ref lit) if name == "regex" => match *lit {
+            Lit::Str(ref pattern) => match regex::Regex::new(&pattern.value()) {
+                // The hdk refuses regex rules at compile time without its `regex` feature
+                Ok(_) => quote_spanned! {pattern.span()=>
+                    ::hdk::hdk_validate_regex!(&self.#ident, #pattern, #field_name)
+                },
+                Err(err) => {
+                    let message = format!("invalid regex of field `{}`: {}", field_name, err);
+                    quote_spanned!(pattern.span()=> compile_error!(#message))
+                }
+            },
+            _ => panic!("regex of field `{}` must be a string literal", field_name),
+        },
+        Rule::List(ref name, ref arguments) if name == "length" => {
+            let (min, max) = bounds(arguments, field_name);
+            quote! {
+                ::hdk::validation::validate_length(&self.#ident, #min, #max, #field_name)
+            }
+        }
+        Rule::List(ref name, ref arguments) if name == "range" => {
+            let (min, max) = bounds(arguments, field_name);
+            quote! {
+                ::hdk::validation::validate_range(&self.#ident, #min, #max, #field_name)
+            }
+        }
+        _ => panic!("unknown validation rule on field `{}`", field_name),
+    }
+}
+
+/// Read the `min = ..` and `max = ..` arguments of a rule as `Option` expressions
+fn bounds(arguments: &Punctuated<Bound, Token![,]>, field_name: &str) -> (TokenStream2, TokenStream2) {
+    let mut min = quote!(None);
+    let mut max = quote!(None);
+    for argument in arguments {
+        let value = &argument.value;
+        if argument.name == "min" {
+            min = quote!(Some(#value));
+        } else if argument.name == "max" {
+            max = quote!(Some(#value));
+        } else {
+            panic!("unknown bound `{}` on field `{}`", argument.name, field_name);
+        }
+    }
+    (min, max)
+}
@@ -1,26 +1,20 @@
+#[macro_use]
 extern crate hdk;
 
 
-fn genesis()
-{
-  let post_hash = hdk::commit_entry("handle", json!(
-        {
-            "content": hdk::APP_AGENT_STR,
-            "version": hdk::VERSION_STR,
-            "app": hdk::APP_NAME,
-        }
-    ));
-
-  hdk::link_entries(hdk::APP_AGENT_HASH, post_hash.clone(), "authored_posts");
-
-  let in_reply_to = input["in_reply_to"].to_string();
-  if in_reply_to != "" {
-    if let Some(_) = hdk::get_entry(in_reply_to.clone()) {
-      hdk::link_entries(in_reply_to, post_hash.clone(), "comments");
-    }
+genesis! {
+  || {
+    hdk::commit_entry("handle", json!(
+          {
+              "content": *hdk::APP_AGENT_ID_STR,
+              "agent": *hdk::APP_AGENT_KEY_HASH,
+              "version": hdk::VERSION_STR,
+              "app": *hdk::APP_NAME,
+          }
+      ))
+      .map(|_| ())
+      .map_err(|err| format!("could not commit handle: {:?}", err))
   }
-
-  json!({"hash": post_hash})
 }
 
 #[no_mangle]
@@ -0,0 +1,74 @@
+//! File holding the structured identity of the agent running the zome
+
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use try_globals;
+use AppGlobals;
+use RibosomeError;
+
+/// The identity of the agent running this zome, as set when its chain was first initialized.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AgentId {
+    identity: String,
+    key_hash: HashString,
+    initial_hash: HashString,
+    latest_hash: HashString,
+}
+
+impl AgentId {
+    pub(crate) fn from_globals(app_globals: &AppGlobals) -> AgentId {
+        AgentId {
+            identity: app_globals.app_agent_id_str.clone(),
+            key_hash: app_globals.app_agent_key_hash.clone(),
+            initial_hash: app_globals.app_agent_initial_hash.clone(),
+            latest_hash: app_globals.app_agent_latest_hash.clone(),
+        }
+    }
+
+    /// The raw identity string, as in `APP_AGENT_ID_STR`
+    pub fn identity(&self) -> &str {
+        &self.identity
+    }
+
+    /// The hash of the agent's public key, as in `APP_AGENT_KEY_HASH`
+    pub fn key_hash(&self) -> &HashString {
+        &self.key_hash
+    }
+
+    /// The hash of the first identity entry, as in `APP_AGENT_INITIAL_HASH`
+    pub fn initial_hash(&self) -> &HashString {
+        &self.initial_hash
+    }
+
+    /// The hash of the latest identity entry, as in `APP_AGENT_LATEST_HASH`
+    pub fn latest_hash(&self) -> &HashString {
+        &self.latest_hash
+    }
+
+    /// Parses the identity string as JSON into an app defined profile.
+    pub fn parse<T: DeserializeOwned>(&self) -> Result<T, RibosomeError> {
+        ::serde_json::from_str(&self.identity).map_err(|err| {
+            RibosomeError::RibosomeFailed(format!("agent identity is not the expected JSON: {}", err))
+        })
+    }
+
+    /// Returns a single property of an identity embedded as a JSON object,
+    /// e.g. `property("FirstName")`.
+    pub fn property(&self, name: &str) -> Option<Value> {
+        self.parse::<Value>()
+            .ok()
+            .and_then(|identity| identity.get(name).cloned())
+    }
+}
+
+/// Returns the identity of the agent running this zome.
+pub fn agent_id() -> Result<AgentId, RibosomeError> {
+    try_globals().map(|app_globals| AgentId::from_globals(&app_globals))
+}
+
+/// Parses the identity string of the agent running this zome as JSON
+/// into an app defined profile struct.
+pub fn agent_id_as<T: DeserializeOwned>() -> Result<T, RibosomeError> {
+    agent_id()?.parse()
+}
@@ -0,0 +1,178 @@
+//! File holding the arguments and results of the zome API functions
+//! not yet covered by holochain_wasm_utils.
+//! The ribosome does not implement these functions yet: only the mock host reads and
+//! writes these structs, so they define the format a ribosome will have to follow.
+
+use holochain_wasm_utils::{
+    api_serialization::{
+        commit::{CommitEntryArgs, CommitEntryResult},
+        get_entry::GetEntryResult,
+    },
+    holochain_core_types::hash::HashString,
+};
+use BridgeSide;
+use EntryStatus;
+use EntryType;
+
+/// A bridge between this DNA and another DNA run by the same agent
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Bridge {
+    pub name: String,
+    /// `From` if this DNA calls into the other one, `To` if it is called by it
+    pub side: BridgeSide,
+    pub dna_hash: HashString,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetBridgesResult {
+    pub bridges: Vec<Bridge>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BridgeCallArgs {
+    pub bridge_name: String,
+    pub zome_name: String,
+    pub function_name: String,
+    pub arguments: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BridgeCallResult {
+    pub result: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SendArgs {
+    pub to: HashString,
+    pub message: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SendResult {
+    pub response: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueryArgs {
+    pub entry_type: EntryType,
+    pub limit: u32,
+    /// Also return the header of each entry found
+    pub headers: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct QueryResult {
+    pub addresses: Vec<HashString>,
+    #[serde(default)]
+    pub headers: Vec<ChainHeader>,
+}
+
+/// The header committed to the source chain along with each entry
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChainHeader {
+    pub entry_type: EntryType,
+    /// Time of the commit, as an ISO 8601 string
+    pub timestamp: String,
+    /// Address of the previous header on the chain, `None` for the first one
+    pub link: Option<HashString>,
+    pub entry_address: HashString,
+    /// Signature of the entry by the agent that committed it
+    pub entry_signature: String,
+    /// Address of the previous header for an entry of the same type
+    pub link_same_type: Option<HashString>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetHeaderArgs {
+    pub entry_address: HashString,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetHeaderResult {
+    pub header: Option<ChainHeader>,
+}
+
+/// Order in which `get_links_with_options` returns links
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub enum LinkSortOrder {
+    /// Whatever order the DHT returns them in
+    Unsorted,
+    /// Oldest link first
+    TimestampAscending,
+    /// Most recent link first
+    TimestampDescending,
+}
+
+impl Default for LinkSortOrder {
+    fn default() -> LinkSortOrder {
+        LinkSortOrder::Unsorted
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct GetLinksOptions {
+    /// Maximum number of links to return, 0 for no limit
+    pub limit: u32,
+    /// `next_page_token` of the previous page, `None` for the first page
+    pub page_token: Option<String>,
+    pub sort: LinkSortOrder,
+    /// Also return links that have been deleted
+    pub include_deleted: bool,
+    /// Also return the content of the target entries, in the same call
+    pub load_entries: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LinkEntriesArgs {
+    pub base: HashString,
+    pub target: HashString,
+    pub tag: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetLinksArgs {
+    pub base: HashString,
+    pub tag: String,
+    pub options: GetLinksOptions,
+}
+
+/// A link from a base entry, as returned by `get_links_with_options`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Link {
+    pub target: HashString,
+    /// Key hash of the agent that added the link
+    pub author: HashString,
+    /// Time the link was added, as an ISO 8601 string
+    pub timestamp: String,
+    pub status: EntryStatus,
+    /// Content of the target entry, if `load_entries` was requested and the entry was found
+    #[serde(default)]
+    pub entry: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetLinksResult {
+    pub links: Vec<Link>,
+    /// Token to get the next page of links, `None` on the last page
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommitEntriesArgs {
+    pub entries: Vec<CommitEntryArgs>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommitEntriesResult {
+    pub results: Vec<CommitEntryResult>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct GetEntriesArgs {
+    pub addresses: Vec<HashString>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GetEntriesResult {
+    pub results: Vec<GetEntryResult>,
+}
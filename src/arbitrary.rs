@@ -0,0 +1,157 @@
+//! File holding the property based testing support of the `arbitrary` feature:
+//! proptest strategies for the values given to validation callbacks, and helpers
+//! checking the callbacks generated by `validations!` against arbitrary entries
+//! in the mock host.
+//!
+//! Entry types plug in through proptest's `Arbitrary` trait, implemented by hand
+//! or derived with `proptest-derive`.
+//!
+//! # Examples
+//! ```ignore
+//! #[derive(Serialize, Deserialize, Debug, Arbitrary)]
+//! struct Post {
+//!     content: String,
+//! }
+//!
+//! validations! {
+//!     [ENTRY] validate_post {
+//!         [hdk::ValidationPackage::Entry]
+//!         |post: Post, _ctx: hdk::ValidationData| {
+//!             if post.content.is_empty() { Err("empty post") } else { Ok(()) }
+//!         }
+//!     }
+//! }
+//!
+//! #[test]
+//! fn validate_post_refuses_empty_posts_only() {
+//!     hdk::arbitrary::check_entry_validation(validate_post, |post: &Post, _ctx| {
+//!         !post.content.is_empty()
+//!     }).unwrap();
+//! }
+//! ```
+
+use holochain_wasm_utils::{
+    api_serialization::validation::ValidationData, holochain_core_types::hash::HashString,
+};
+use mock::{self, validation_data_json, ZomeFunction};
+use proptest::{
+    prelude::*,
+    test_runner::{TestError, TestRunner},
+};
+use serde::Serialize;
+use std::fmt::Debug;
+use {EntryType, LinkAction, SystemEntryType, ValidationError};
+
+/// Key hashes generated validation data is signed with,
+/// starting with the key hash of the mock agent
+pub const AGENT_KEY_HASHES: &[&str] = &[
+    "QmMockAgentKey",
+    "QmMockOtherAgentKey",
+    "QmMockThirdAgentKey",
+];
+
+/// Strategy for strings shaped like base58 multihashes, e.g. link bases and targets
+pub fn hash_string() -> impl Strategy<Value = HashString> {
+    "Qm[1-9A-HJ-NP-Za-km-z]{44}".prop_map(HashString::from)
+}
+
+/// Strategy for the key hash of one of the `AGENT_KEY_HASHES`
+pub fn agent_key_hash() -> impl Strategy<Value = HashString> {
+    prop::sample::select(AGENT_KEY_HASHES).prop_map(HashString::from)
+}
+
+/// Strategy for the validation data given to validation callbacks:
+/// one to three signing agents, any lifecycle and any action
+pub fn validation_data() -> impl Strategy<Value = ValidationData> {
+    (
+        prop::collection::vec(agent_key_hash(), 1..4),
+        prop::sample::select(vec!["Chain", "Dht", "Meta"]),
+        prop::sample::select(vec!["Commit", "Modify", "Delete"]),
+    ).prop_map(|(sources, lifecycle, action)| {
+        let json = validation_data_json(&sources, lifecycle, action);
+        ::serde_json::from_value(json).expect("mock validation data should be a ValidationData")
+    })
+}
+
+impl Arbitrary for SystemEntryType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<SystemEntryType>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        prop_oneof![
+            Just(SystemEntryType::Dna),
+            Just(SystemEntryType::Agent),
+            Just(SystemEntryType::Key),
+            Just(SystemEntryType::Headers),
+            Just(SystemEntryType::Deletion),
+        ].boxed()
+    }
+}
+
+impl Arbitrary for EntryType {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<EntryType>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        prop_oneof![
+            "[a-z][a-zA-Z0-9_]{0,15}".prop_map(EntryType::App),
+            any::<SystemEntryType>().prop_map(EntryType::System),
+        ].boxed()
+    }
+}
+
+impl Arbitrary for LinkAction {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<LinkAction>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        prop_oneof![Just(LinkAction::Add), Just(LinkAction::Delete)].boxed()
+    }
+}
+
+/// Runs an `[ENTRY]` validation callback against `entry` in the mock host
+pub fn run_entry_validation<E: Serialize>(
+    validation_callback: ZomeFunction,
+    entry: &E,
+    ctx: &ValidationData,
+) -> Result<(), Vec<ValidationError>> {
+    mock::validate(validation_callback, json!({ "entry": entry, "ctx": ctx }))
+}
+
+/// Checks an `[ENTRY]` validation callback against arbitrary entries and validation data:
+/// the callback must accept exactly the entries `expected` accepts.
+/// Returns the simplest inconsistent case found.
+pub fn check_entry_validation<E, F>(
+    validation_callback: ZomeFunction,
+    expected: F,
+) -> Result<(), TestError<(E, ValidationData)>>
+where
+    E: Arbitrary + Serialize,
+    F: Fn(&E, &ValidationData) -> bool,
+{
+    check_entry_validation_with(validation_callback, any::<E>(), expected)
+}
+
+/// Same as `check_entry_validation`, with the entries generated by `entries`
+pub fn check_entry_validation_with<S, F>(
+    validation_callback: ZomeFunction,
+    entries: S,
+    expected: F,
+) -> Result<(), TestError<(S::Value, ValidationData)>>
+where
+    S: Strategy,
+    S::Value: Serialize + Debug,
+    F: Fn(&S::Value, &ValidationData) -> bool,
+{
+    mock::reset();
+    TestRunner::default().run(&(entries, validation_data()), |(entry, ctx)| {
+        let result = run_entry_validation(validation_callback, &entry, &ctx);
+        prop_assert_eq!(
+            result.is_ok(),
+            expected(&entry, &ctx),
+            "validation callback returned {:?}",
+            result
+        );
+        Ok(())
+    })
+}
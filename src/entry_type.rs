@@ -0,0 +1,160 @@
+//! File holding the types naming the kind of an entry
+
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+// Prefix marking the names of system entry types
+const SYSTEM_ENTRY_TYPE_PREFIX: &str = "%";
+
+// HC.SysEntryType
+// WARNING Keep in sync with SystemEntryType in holochain-rust
+/// Entries committed by Holochain itself rather than by the app
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SystemEntryType {
+    #[serde(rename = "%dna")]
+    Dna,
+    #[serde(rename = "%agent_id")]
+    Agent,
+    #[serde(rename = "%key")]
+    Key,
+    #[serde(rename = "%chain_header")]
+    Headers,
+    #[serde(rename = "%deletion")]
+    Deletion,
+}
+
+impl SystemEntryType {
+    /// The name of the entry type, as used by the ribosome
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SystemEntryType::Dna => "%dna",
+            SystemEntryType::Agent => "%agent_id",
+            SystemEntryType::Key => "%key",
+            SystemEntryType::Headers => "%chain_header",
+            SystemEntryType::Deletion => "%deletion",
+        }
+    }
+
+    /// Returns the system entry type with this name
+    pub fn from_name(name: &str) -> Option<SystemEntryType> {
+        [
+            SystemEntryType::Dna,
+            SystemEntryType::Agent,
+            SystemEntryType::Key,
+            SystemEntryType::Headers,
+            SystemEntryType::Deletion,
+        ].iter()
+            .find(|entry_type| entry_type.as_str() == name)
+            .cloned()
+    }
+}
+
+/// The type of an entry: either defined by the app in its DNA, or a system entry type.
+/// (De)serializes as the name of the entry type, system entry types being prefixed with `%`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum EntryType {
+    App(String),
+    System(SystemEntryType),
+}
+
+impl EntryType {
+    /// Parses the name of an entry type, as used by the ribosome
+    pub fn from_name(name: &str) -> Result<EntryType, String> {
+        if name.starts_with(SYSTEM_ENTRY_TYPE_PREFIX) {
+            SystemEntryType::from_name(name)
+                .map(EntryType::System)
+                .ok_or_else(|| format!("unknown system entry type: {}", name))
+        } else {
+            Ok(EntryType::App(name.to_string()))
+        }
+    }
+
+    pub fn is_system(&self) -> bool {
+        match self {
+            EntryType::System(_) => true,
+            EntryType::App(_) => false,
+        }
+    }
+}
+
+impl fmt::Display for EntryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EntryType::App(name) => write!(f, "{}", name),
+            EntryType::System(system_entry_type) => write!(f, "{}", system_entry_type.as_str()),
+        }
+    }
+}
+
+impl<'a> From<&'a str> for EntryType {
+    fn from(name: &str) -> EntryType {
+        EntryType::App(name.to_string())
+    }
+}
+
+impl From<SystemEntryType> for EntryType {
+    fn from(system_entry_type: SystemEntryType) -> EntryType {
+        EntryType::System(system_entry_type)
+    }
+}
+
+impl Serialize for EntryType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for EntryType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<EntryType, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        EntryType::from_name(&name).map_err(de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json;
+
+    const SYSTEM_ENTRY_TYPES: [(SystemEntryType, &'static str); 5] = [
+        (SystemEntryType::Dna, "%dna"),
+        (SystemEntryType::Agent, "%agent_id"),
+        (SystemEntryType::Key, "%key"),
+        (SystemEntryType::Headers, "%chain_header"),
+        (SystemEntryType::Deletion, "%deletion"),
+    ];
+
+    #[test]
+    fn system_entry_types_round_trip_through_their_names() {
+        for &(system_entry_type, name) in SYSTEM_ENTRY_TYPES.iter() {
+            let json = serde_json::to_string(&system_entry_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", name));
+            assert_eq!(serde_json::from_str::<SystemEntryType>(&json).unwrap(), system_entry_type);
+            assert_eq!(SystemEntryType::from_name(name), Some(system_entry_type));
+        }
+        assert_eq!(SystemEntryType::from_name("%unknown"), None);
+    }
+
+    #[test]
+    fn entry_types_round_trip_through_their_names() {
+        for &(system_entry_type, name) in SYSTEM_ENTRY_TYPES.iter() {
+            let entry_type = EntryType::from(system_entry_type);
+            let json = serde_json::to_string(&entry_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", name));
+            assert_eq!(serde_json::from_str::<EntryType>(&json).unwrap(), entry_type);
+            assert!(entry_type.is_system());
+        }
+
+        let entry_type = EntryType::from("post");
+        let json = serde_json::to_string(&entry_type).unwrap();
+        assert_eq!(json, "\"post\"");
+        assert_eq!(serde_json::from_str::<EntryType>(&json).unwrap(), entry_type);
+        assert!(!entry_type.is_system());
+    }
+
+    #[test]
+    fn unknown_system_entry_types_are_rejected() {
+        let error = serde_json::from_str::<EntryType>("\"%unknown\"").unwrap_err();
+        assert!(error.to_string().starts_with("unknown system entry type: %unknown"));
+    }
+}
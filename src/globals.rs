@@ -1,19 +1,121 @@
 //! File for holding all internal/private globals used by the zome api library
 
 use holochain_wasm_utils::memory_allocation::SinglePageStack;
-use init_globals::init_globals;
 use init_globals::AppGlobals;
+#[cfg(feature = "mock")]
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use try_globals;
 
 // Internal global for memory usage
 pub static mut G_MEM_STACK: Option<SinglePageStack> = None;
 
+// Internal global caching the app globals once retrieved from the ribosome.
+// Refreshing them replaces the cached value, dropped once no caller holds it.
+#[cfg(not(feature = "mock"))]
+static mut G_APP_GLOBALS: Option<Rc<AppGlobals>> = None;
+
+#[cfg(not(feature = "mock"))]
+pub(crate) fn cached_app_globals() -> Option<Rc<AppGlobals>> {
+    unsafe { G_APP_GLOBALS.clone() }
+}
+
+#[cfg(not(feature = "mock"))]
+pub(crate) fn cache_app_globals(app_globals: Option<Rc<AppGlobals>>) {
+    unsafe {
+        G_APP_GLOBALS = app_globals;
+    }
+}
+
+// Tests run concurrently natively, each with its own mock host
+#[cfg(feature = "mock")]
+thread_local! {
+    static G_APP_GLOBALS: RefCell<Option<Rc<AppGlobals>>> = RefCell::new(None);
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn cached_app_globals() -> Option<Rc<AppGlobals>> {
+    G_APP_GLOBALS.with(|app_globals| app_globals.borrow().clone())
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn cache_app_globals(app_globals: Option<Rc<AppGlobals>>) {
+    G_APP_GLOBALS.with(|cached| *cached.borrow_mut() = app_globals);
+}
+
+// Internal global holding the name of the zome function being run
+#[cfg(not(feature = "mock"))]
+static mut G_ZOME_FUNCTION: Option<&'static str> = None;
+
+#[cfg(not(feature = "mock"))]
+pub(crate) fn current_zome_function() -> Option<&'static str> {
+    unsafe { G_ZOME_FUNCTION }
+}
+
+#[cfg(not(feature = "mock"))]
+pub(crate) fn set_current_zome_function(function: Option<&'static str>) {
+    unsafe {
+        G_ZOME_FUNCTION = function;
+    }
+}
+
+#[cfg(feature = "mock")]
+thread_local! {
+    static G_ZOME_FUNCTION: Cell<Option<&'static str>> = Cell::new(None);
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn current_zome_function() -> Option<&'static str> {
+    G_ZOME_FUNCTION.with(|function| function.get())
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn set_current_zome_function(function: Option<&'static str>) {
+    G_ZOME_FUNCTION.with(|current| current.set(function));
+}
+
+// Internal global recording that the zome API version of the ribosome was checked
+#[cfg(not(feature = "mock"))]
+static mut G_API_VERSION_CHECKED: bool = false;
+
+#[cfg(not(feature = "mock"))]
+pub(crate) fn api_version_checked() -> bool {
+    unsafe { G_API_VERSION_CHECKED }
+}
+
+#[cfg(not(feature = "mock"))]
+pub(crate) fn set_api_version_checked(checked: bool) {
+    unsafe {
+        G_API_VERSION_CHECKED = checked;
+    }
+}
+
+#[cfg(feature = "mock")]
+thread_local! {
+    static G_API_VERSION_CHECKED: Cell<bool> = Cell::new(false);
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn api_version_checked() -> bool {
+    G_API_VERSION_CHECKED.with(|checked| checked.get())
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn set_api_version_checked(checked: bool) {
+    G_API_VERSION_CHECKED.with(|current| current.set(checked));
+}
+
 // Internal global for retrieving all app globals
 lazy_static! {
-    pub(crate) static ref APP_GLOBALS: AppGlobals = init_globals();
+    pub(crate) static ref APP_GLOBALS: AppGlobals = match try_globals() {
+        Ok(app_globals) => (*app_globals).clone(),
+        Err(err) => panic!("AppGlobals should be available: {:?}", err),
+    };
 }
 
 // Invokable functions in the ribosome
 // WARNING Names must be in sync with ZomeAPIFunction in holochain-rust
+#[cfg(not(feature = "mock"))]
 extern "C" {
     pub(crate) fn hc_property(encoded_allocation_of_input: u32) -> u32;
     pub(crate) fn hc_make_hash(encoded_allocation_of_input: u32) -> u32;
@@ -32,3 +134,10 @@ extern "C" {
     pub(crate) fn hc_start_bundle(encoded_allocation_of_input: u32) -> u32;
     pub(crate) fn hc_close_bundle(encoded_allocation_of_input: u32) -> u32;
 }
+
+// Functions of the mock host standing in for the ribosome when running natively
+#[cfg(feature = "mock")]
+pub(crate) use mock::host::{
+    hc_bridge_call, hc_commit_entries, hc_commit_entry, hc_debug, hc_get_bridges, hc_get_entries,
+    hc_get_entry, hc_get_header, hc_get_links, hc_link_entries, hc_query, hc_send,
+};
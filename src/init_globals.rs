@@ -1,34 +1,109 @@
 //! File for holding the internal/private zome api function `init_globals`
 
-use holochain_wasm_utils::{
-    holochain_core_types::hash::HashString,
-    memory_serialization::try_deserialize_allocation,
-};
+use deserialize_wasm_input;
+use globals::cache_app_globals;
+use metrics;
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+#[cfg(feature = "mock")]
+use mock::host::hc_init_globals;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::rc::Rc;
+use CallProvenance;
+use RibosomeError;
 
+#[cfg(not(feature = "mock"))]
 extern "C" {
     fn hc_init_globals(encoded_allocation_of_input: u32) -> u32;
 }
 
 // WARNING must be in sync with InitGlobalsOutput in core
-#[derive(Deserialize, Clone)]
-pub(crate) struct AppGlobals {
+// Fields unknown to this version of the hdk are ignored,
+// so the ribosome can add globals without breaking older zomes.
+#[derive(Clone, Debug)]
+pub struct AppGlobals {
     pub app_name: String,
     pub app_dna_hash: HashString,
     pub app_agent_id_str: String,
     pub app_agent_key_hash: HashString,
     pub app_agent_initial_hash: HashString,
     pub app_agent_latest_hash: HashString,
+    // Optional: not provided by older ribosomes
+    pub api_version: Option<u16>,
+    pub zome_name: Option<String>,
+    pub capability_name: Option<String>,
+    pub call_provenance: Option<CallProvenance>,
+    /// Milliseconds since the UNIX epoch, as measured by the host
+    pub timestamp: Option<u64>,
+}
+
+impl AppGlobals {
+    /// Read the globals out of the JSON object sent by the ribosome.
+    /// The error names the first field that is missing or of the wrong type.
+    pub fn from_json(globals: &Value) -> Result<AppGlobals, String> {
+        if !globals.is_object() {
+            return Err(format!("AppGlobals should be a JSON object, got: {}", globals));
+        }
+        let app_agent_initial_hash: HashString = required_field(globals, "app_agent_initial_hash")?;
+        Ok(AppGlobals {
+            app_name: required_field(globals, "app_name")?,
+            app_dna_hash: required_field(globals, "app_dna_hash")?,
+            app_agent_id_str: required_field(globals, "app_agent_id_str")?,
+            app_agent_key_hash: required_field(globals, "app_agent_key_hash")?,
+            // Hosts that do not track agent updates yet only know the initial identity entry
+            app_agent_latest_hash: optional_field(globals, "app_agent_latest_hash")?
+                .unwrap_or_else(|| app_agent_initial_hash.clone()),
+            app_agent_initial_hash,
+            api_version: optional_field(globals, "api_version")?,
+            zome_name: optional_field(globals, "zome_name")?,
+            capability_name: optional_field(globals, "capability_name")?,
+            call_provenance: optional_field(globals, "call_provenance")?,
+            timestamp: optional_field(globals, "timestamp")?,
+        })
+    }
+}
+
+fn required_field<T: DeserializeOwned>(globals: &Value, name: &str) -> Result<T, String> {
+    optional_field(globals, name)?.ok_or_else(|| format!("AppGlobals field `{}` is missing", name))
+}
+
+fn optional_field<T: DeserializeOwned>(globals: &Value, name: &str) -> Result<Option<T>, String> {
+    match globals.get(name) {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => ::serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|err| format!("AppGlobals field `{}` is invalid: {}", name, err)),
+    }
 }
 
 // HC INIT GLOBALS - Secret Api Function
 // Retrieve all the public global values from the ribosome
-pub(crate) fn init_globals() -> AppGlobals {
+pub(crate) fn init_globals() -> Result<AppGlobals, RibosomeError> {
+    let globals = read_globals()?;
+    AppGlobals::from_json(&globals).map_err(RibosomeError::RibosomeFailed)
+}
+
+/// Version of the zome API implemented by the ribosome, read without the other globals.
+/// `None` if the ribosome does not report it, an error if its globals can not be read.
+/// Valid globals are cached, so the zome function does not read them again.
+pub(crate) fn host_api_version() -> Result<Option<u16>, RibosomeError> {
+    let globals = read_globals()?;
+    if let Ok(app_globals) = AppGlobals::from_json(&globals) {
+        cache_app_globals(Some(Rc::new(app_globals)));
+    }
+    Ok(optional_field(&globals, "api_version").unwrap_or(None))
+}
+
+// Retrieve the JSON object holding the globals from the ribosome
+fn read_globals() -> Result<Value, RibosomeError> {
     // Call WASMI-able init_globals
-    let encoded_allocation_of_result = unsafe { hc_init_globals(0) };
+    let encoded_allocation_of_result =
+        unsafe { host_call!(metrics::call(hc_init_globals, 0)) };
     // Deserialize complex result stored in memory
-    let result = try_deserialize_allocation(encoded_allocation_of_result as u32);
-    if result.is_err() {
-        panic!("AppGlobals should deserialize properly");
-    }
-    result.unwrap()
+    let result: Result<Value, String> = deserialize_wasm_input(encoded_allocation_of_result as u32);
+    #[cfg(feature = "record")]
+    ::record::host_call("hc_init_globals", &Value::Null, &result);
+    result.map_err(|err_str| {
+        RibosomeError::RibosomeFailed(format!("AppGlobals could not be read: {}", err_str))
+    })
 }
@@ -10,14 +10,52 @@ extern crate serde_derive;
 extern crate bitflags;
 #[macro_use]
 extern crate lazy_static;
+extern crate log;
+#[cfg(feature = "regex")]
+extern crate regex;
+#[cfg(feature = "arbitrary")]
+#[macro_use]
+extern crate proptest;
 pub extern crate holochain_wasm_utils;
 
+// Calls `$call` with the name of the host function `$ribosome_fn` followed by the function
+// itself, e.g. `host_call!(call_ribosome(hc_get_entry, input))`, so the name the metrics and
+// the recording report can not differ from the function called
+macro_rules! host_call {
+    ($($call:ident)::+ ($ribosome_fn:ident $(, $arg:expr)*)) => (
+        $($call)::+(stringify!($ribosome_fn), $ribosome_fn $(, $arg)*)
+    );
+}
+
+pub mod agent;
+pub mod api_serialization;
+#[cfg(feature = "arbitrary")]
+pub mod arbitrary;
+pub mod entry_type;
 pub mod globals;
 pub mod init_globals;
+pub mod logging;
 pub mod macros;
+pub mod metrics;
+#[cfg(feature = "mock")]
+pub mod mock;
+pub mod record;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod testing;
+pub mod validation;
+pub mod zome_call;
 
 use self::RibosomeError::*;
+pub use agent::{agent_id, agent_id_as, AgentId};
+#[cfg(feature = "mock")]
+use api_serialization::*;
+pub use api_serialization::{
+    Bridge, ChainHeader, GetLinksOptions, GetLinksResult, Link, LinkSortOrder,
+};
+pub use entry_type::{EntryType, SystemEntryType};
 use globals::*;
+pub use init_globals::AppGlobals;
+use init_globals::{host_api_version, init_globals};
 use holochain_wasm_utils::{
     api_serialization::{
         commit::{CommitEntryArgs, CommitEntryResult},
@@ -25,10 +63,15 @@ use holochain_wasm_utils::{
         validation::*,
     },
     holochain_core_types::hash::HashString,
-    memory_serialization::*, memory_allocation::*,
 };
+#[cfg(not(feature = "mock"))]
+use holochain_wasm_utils::{memory_allocation::*, memory_serialization::*};
+use std::fmt;
+use std::rc::Rc;
 pub use holochain_wasm_utils::api_serialization::validation::*;
+pub use validation::{IntoValidationErrors, Validate, ValidationContext, ValidationError};
 
+#[cfg(not(feature = "mock"))]
 pub fn init_memory_stack(encoded_allocation_of_input: u32) {
     // Actual program
     // Init memory stack
@@ -38,6 +81,22 @@ pub fn init_memory_stack(encoded_allocation_of_input: u32) {
     }
 }
 
+// The mock memory needs no stack
+#[cfg(feature = "mock")]
+pub fn init_memory_stack(_encoded_allocation_of_input: u32) {}
+
+/// Whether the memory stack is set up, so host functions can be called
+#[cfg(not(feature = "mock"))]
+pub(crate) fn memory_stack_ready() -> bool {
+    unsafe { G_MEM_STACK.is_some() }
+}
+
+#[cfg(feature = "mock")]
+pub(crate) fn memory_stack_ready() -> bool {
+    true
+}
+
+#[cfg(not(feature = "mock"))]
 pub fn serialize_wasm_output<T: serde::Serialize>(output: T) -> u32
 {
     // Serialize output in WASM memory
@@ -46,11 +105,50 @@ pub fn serialize_wasm_output<T: serde::Serialize>(output: T) -> u32
     }
 }
 
+#[cfg(feature = "mock")]
+pub fn serialize_wasm_output<T: serde::Serialize>(output: T) -> u32 {
+    mock::memory::store_value(&output).expect("output should serialize to JSON")
+}
+
+/// Deserializes a value stored in memory by the ribosome,
+/// e.g. the input of a zome function or the result of an API function.
+#[cfg(not(feature = "mock"))]
+pub fn deserialize_wasm_input<T: serde::de::DeserializeOwned>(
+    encoded_allocation: u32,
+) -> Result<T, String> {
+    try_deserialize_allocation(encoded_allocation)
+}
+
+#[cfg(feature = "mock")]
+pub fn deserialize_wasm_input<T: serde::de::DeserializeOwned>(
+    encoded_allocation: u32,
+) -> Result<T, String> {
+    mock::memory::take_value(encoded_allocation)
+}
+
 
 //--------------------------------------------------------------------------------------------------
 // APP GLOBAL VARIABLES
 //--------------------------------------------------------------------------------------------------
 
+/// Returns the app globals, retrieving them from the ribosome on first use.
+/// Unlike the `APP_*` variables below, which panic when the globals are unavailable,
+/// this reports the field the ribosome sent in an unexpected shape.
+pub fn try_globals() -> Result<Rc<AppGlobals>, RibosomeError> {
+    if let Some(app_globals) = cached_app_globals() {
+        return Ok(app_globals);
+    }
+    refresh_globals()
+}
+
+/// Retrieves the app globals from the ribosome again, e.g. after a call to `update_agent`.
+/// The `APP_*` variables below keep the values they were first read with.
+pub fn refresh_globals() -> Result<Rc<AppGlobals>, RibosomeError> {
+    let app_globals = Rc::new(init_globals()?);
+    cache_app_globals(Some(app_globals.clone()));
+    Ok(app_globals)
+}
+
 lazy_static! {
   /// The name of this Holochain taken from its DNA.
   pub static ref APP_NAME: &'static str = &APP_GLOBALS.app_name;
@@ -61,7 +159,8 @@ lazy_static! {
 
   /// The identity string used when the chain was first initialized.
   /// If you used JSON to embed multiple properties (such as FirstName, LastName, Email, etc),
-  /// they can be retrieved here as App.Agent.FirstName, etc. (FIXME)
+  /// they can be retrieved with `agent_id()?.property("FirstName")`,
+  /// or all at once into your own struct with `agent_id_as::<Profile>()`.
   pub static ref APP_AGENT_ID_STR: &'static str = &APP_GLOBALS.app_agent_id_str;
 
   /// The hash of your public key.
@@ -79,13 +178,71 @@ lazy_static! {
   pub static ref APP_AGENT_LATEST_HASH: &'static HashString = &APP_GLOBALS.app_agent_latest_hash;
 }
 
+// The globals below describe the current zome call: they are read again
+// from the ribosome for each call, instead of once like the `APP_*` variables.
+
+/// The name of the zome being executed.
+/// `None` if the ribosome does not provide it.
+pub fn zome_name() -> Result<Option<String>, RibosomeError> {
+    Ok(try_globals()?.zome_name.clone())
+}
+
+/// The name of the capability through which the current function was called.
+/// `None` if the ribosome does not provide it.
+pub fn capability_name() -> Result<Option<String>, RibosomeError> {
+    Ok(try_globals()?.capability_name.clone())
+}
+
+/// Who invoked the current zome call: the local UI, a bridged app or a remote agent.
+/// `None` if the ribosome does not provide it.
+pub fn call_provenance() -> Result<Option<CallProvenance>, RibosomeError> {
+    Ok(try_globals()?.call_provenance.clone())
+}
+
+/// The host's time when the current zome call started, in milliseconds since the UNIX epoch.
+/// `None` if the ribosome does not provide it.
+pub fn call_timestamp() -> Result<Option<u64>, RibosomeError> {
+    Ok(try_globals()?.timestamp)
+}
+
 //--------------------------------------------------------------------------------------------------
 // SYSTEM CONSTS
 //--------------------------------------------------------------------------------------------------
 
 // HC.Version
-const VERSION: u16 = 1;
-const VERSION_STR: &'static str = "1";
+/// Version of the zome API (the `hc_*` functions) this hdk is written against
+pub const VERSION: u16 = 1;
+pub const VERSION_STR: &str = "1";
+
+/// Exposes the zome API version of this hdk to the ribosome
+#[no_mangle]
+pub extern "C" fn __hdk_version() -> u32 {
+    VERSION as u32
+}
+
+/// Checks that the ribosome implements the zome API version this hdk is written against,
+/// so a mismatched ribosome is reported before any `hc_*` function is called.
+/// Ribosomes that do not report their version, or whose globals can not be read,
+/// are assumed to be compatible.
+/// Called by the zome macros on entry of every generated function: only the `api_version`
+/// global is read, and only until it was read successfully and matched.
+pub fn check_api_version() -> Result<(), RibosomeError> {
+    if api_version_checked() {
+        return Ok(());
+    }
+    match host_api_version() {
+        Ok(Some(host_version)) if host_version != VERSION => Err(ApiVersionMismatch {
+            hdk: VERSION,
+            host: host_version,
+        }),
+        Ok(_) => {
+            set_api_version_checked(true);
+            Ok(())
+        }
+        // Checked again by the next call, once the globals can be read
+        Err(_) => Ok(()),
+    }
+}
 
 // HC.HashNotFound
 #[derive(Debug)]
@@ -93,18 +250,42 @@ pub enum RibosomeError {
     RibosomeFailed(String),
     FunctionNotImplemented,
     HashNotFound,
-    ValidationFailed(String)
+    ValidationFailed(Vec<ValidationError>),
+    ApiVersionMismatch { hdk: u16, host: u16 },
+    ZomeFunctionPanicked { message: String, location: Option<String> },
+}
+
+impl fmt::Display for RibosomeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RibosomeFailed(error_desc) => write!(f, "{}", error_desc),
+            FunctionNotImplemented => write!(f, "Function not implemented"),
+            HashNotFound => write!(f, "Hash not found"),
+            ValidationFailed(errors) => write!(
+                f,
+                "Validation failed: {}",
+                errors
+                    .iter()
+                    .map(|error| error.message.clone())
+                    .collect::<Vec<String>>()
+                    .join("; ")
+            ),
+            ApiVersionMismatch { hdk, host } => write!(
+                f,
+                "Zome API version mismatch: hdk implements version {} but the ribosome implements version {}",
+                hdk, host
+            ),
+            ZomeFunctionPanicked { message, location } => match location {
+                Some(location) => write!(f, "Zome function panicked at {}: {}", location, message),
+                None => write!(f, "Zome function panicked: {}", message),
+            },
+        }
+    }
 }
 
 impl RibosomeError {
     pub fn to_json(&self) -> serde_json::Value {
-        let err_str = match self {
-            RibosomeFailed(error_desc) => error_desc.clone(),
-            FunctionNotImplemented => "Function not implemented".to_string(),
-            HashNotFound => "Hash not found".to_string(),
-            ValidationFailed(msg) => format!("Validation failed: {}", msg),
-        };
-        json!({ "error": err_str })
+        json!({ "error": self.to_string() })
     }
 }
 
@@ -119,6 +300,21 @@ bitflags! {
   }
 }
 
+// (De)serialized as its bits, like CRUDStatus
+impl serde::Serialize for EntryStatus {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_u8(self.bits())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for EntryStatus {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<EntryStatus, D::Error> {
+        let bits = u8::deserialize(deserializer)?;
+        EntryStatus::from_bits(bits)
+            .ok_or_else(|| serde::de::Error::custom(format!("invalid entry status: {}", bits)))
+    }
+}
+
 // HC.GetMask
 bitflags! {
   pub struct GetEntryMask: u8 {
@@ -135,6 +331,7 @@ impl Default for GetEntryMask {
 }
 
 // HC.LinkAction
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum LinkAction {
     Add,
     Delete,
@@ -155,22 +352,25 @@ pub enum ChainOption {
     Full,
 }
 
+// Origin of the current zome call
+// WARNING keep in sync with the call provenance in InitGlobalsOutput
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum CallProvenance {
+    /// The local user interface or conductor
+    Local,
+    /// Another app of this agent, through a bridge from the DNA with this hash
+    Bridge(HashString),
+    /// The remote agent with this key hash
+    Remote(HashString),
+}
+
 // HC.Bridge
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub enum BridgeSide {
     From,
     To,
 }
 
-// HC.SysEntryType
-// WARNING Keep in sync with SystemEntryType in holochain-rust
-enum SystemEntryType {
-    Dna,
-    Agent,
-    Key,
-    Headers,
-    Deletion,
-}
-
 mod bundle_cancel {
     // HC.BundleCancel.Reason
     pub enum Reason {
@@ -194,6 +394,85 @@ pub enum BundleOnClose {
 // API FUNCTIONS
 //--------------------------------------------------------------------------------------------------
 
+/// Serializes the input into the memory stack, calls the ribosome function `function` with it
+/// and deserializes the result it stored in memory.
+#[cfg(not(feature = "record"))]
+fn call_ribosome<I: serde::Serialize, O: serde::de::DeserializeOwned>(
+    function: &'static str,
+    ribosome_fn: unsafe extern "C" fn(u32) -> u32,
+    input: I,
+) -> Result<O, RibosomeError> {
+    // Deserialize complex result stored in memory and check for ERROR in encoding
+    call_ribosome_with(function, ribosome_fn, input, deserialize_wasm_input)?
+        .map_err(RibosomeError::RibosomeFailed)
+}
+
+// Same, recording the input and the result of the call
+#[cfg(feature = "record")]
+fn call_ribosome<I: serde::Serialize, O: serde::de::DeserializeOwned>(
+    function: &'static str,
+    ribosome_fn: unsafe extern "C" fn(u32) -> u32,
+    input: I,
+) -> Result<O, RibosomeError> {
+    let input = serde_json::to_value(input)
+        .map_err(|err| RibosomeError::RibosomeFailed(err.to_string()))?;
+    let output: Result<serde_json::Value, String> =
+        call_ribosome_with(function, ribosome_fn, &input, deserialize_wasm_input)?;
+    record::host_call(function, &input, &output);
+    output
+        .and_then(|output| serde_json::from_value(output).map_err(|err| err.to_string()))
+        .map_err(RibosomeError::RibosomeFailed)
+}
+
+/// Serializes the input into the memory stack, calls the ribosome function `function` with it
+/// and reads its encoded result with `read_result` before freeing the input.
+#[cfg(not(feature = "mock"))]
+fn call_ribosome_with<I, R, F>(
+    function: &'static str,
+    ribosome_fn: unsafe extern "C" fn(u32) -> u32,
+    input: I,
+    read_result: F,
+) -> Result<R, RibosomeError>
+where
+    I: serde::Serialize,
+    F: FnOnce(u32) -> R,
+{
+    let mut mem_stack = unsafe { G_MEM_STACK.unwrap() };
+    let allocation_of_input = serialize(&mut mem_stack, input)
+        .map_err(|err_code| RibosomeError::RibosomeFailed(err_code.to_string()))?;
+
+    // Call WASMI-able function
+    let encoded_allocation_of_result =
+        unsafe { metrics::call(function, ribosome_fn, allocation_of_input.encode() as u32) };
+    let result = read_result(encoded_allocation_of_result);
+
+    // Free result & input allocations and all allocations made inside the ribosome function
+    mem_stack
+        .deallocate(allocation_of_input)
+        .expect("deallocate failed");
+
+    Ok(result)
+}
+
+// The mock host takes its input from the mock memory and stores its result there
+#[cfg(feature = "mock")]
+fn call_ribosome_with<I, R, F>(
+    function: &'static str,
+    ribosome_fn: unsafe extern "C" fn(u32) -> u32,
+    input: I,
+    read_result: F,
+) -> Result<R, RibosomeError>
+where
+    I: serde::Serialize,
+    F: FnOnce(u32) -> R,
+{
+    let encoded_allocation_of_input =
+        mock::memory::store_value(&input).map_err(RibosomeError::RibosomeFailed)?;
+    let encoded_allocation_of_result =
+        unsafe { metrics::call(function, ribosome_fn, encoded_allocation_of_input) };
+    Ok(read_result(encoded_allocation_of_result))
+}
+
 /// FIXME DOC
 /// Returns an application property, which are defined by the app developer.
 /// It returns values from the DNA file that you set as properties of your application
@@ -214,19 +493,8 @@ pub fn make_hash<S: Into<String>>(
 
 /// FIXME DOC
 pub fn debug(msg: &str) -> Result<(), RibosomeError> {
-    let mut mem_stack = unsafe { G_MEM_STACK.unwrap() };
-    let maybe_allocation_of_input = serialize(&mut mem_stack, msg);
-    if let Err(err_code) = maybe_allocation_of_input {
-        return Err(RibosomeError::RibosomeFailed(err_code.to_string()));
-    }
-    let allocation_of_input = maybe_allocation_of_input.unwrap();
-    unsafe {
-        hc_debug(allocation_of_input.encode());
-    }
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("should be able to deallocate input that has been allocated on memory stack");
-    Ok(())
+    // hc_debug has no result to read
+    host_call!(call_ribosome_with(hc_debug, msg, |_| ()))
 }
 
 /// FIXME DOC
@@ -260,42 +528,53 @@ pub fn commit_entry(
     entry_type_name: &str,
     entry_content: serde_json::Value,
 ) -> Result<HashString, RibosomeError> {
-    let mut mem_stack: SinglePageStack;
-    unsafe {
-        mem_stack = G_MEM_STACK.unwrap();
-    }
-
     // Put args in struct and serialize into memory
     let input = CommitEntryArgs {
         entry_type_name: entry_type_name.to_string(),
         entry_value: entry_content.to_string(),
     };
-    let maybe_allocation_of_input = serialize(&mut mem_stack, input);
-    if let Err(err_code) = maybe_allocation_of_input {
-        return Err(RibosomeError::RibosomeFailed(err_code.to_string()));
-    }
-    let allocation_of_input = maybe_allocation_of_input.unwrap();
-
-    // Call WASMI-able commit
-    let encoded_allocation_of_result: u32;
-    unsafe {
-        encoded_allocation_of_result = hc_commit_entry(allocation_of_input.encode() as u32);
-    }
-    // Deserialize complex result stored in memory and check for ERROR in encoding
-    let result = try_deserialize_allocation(encoded_allocation_of_result as u32);
-
-    if let Err(err_str) = result {
-        return Err(RibosomeError::RibosomeFailed(err_str));
-    }
-    let output: CommitEntryResult = result.unwrap();
+    let output: CommitEntryResult = host_call!(call_ribosome(hc_commit_entry, input))?;
+    commit_entry_result(output)
+}
+
+/// Commits several entries, passing them to the ribosome in a single call.
+/// Returns the result of each commit, in the order of `entries`.
+/// Batches are not atomic: each entry is committed on its own, so an entry failing
+/// validation does not prevent the others from being committed, and when the whole
+/// call fails the entries before the failing one may already be committed.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn commit_entries<S: Into<String>>(
+    entries: Vec<(S, serde_json::Value)>,
+) -> Result<Vec<Result<HashString, RibosomeError>>, RibosomeError> {
+    let input = CommitEntriesArgs {
+        entries: entries
+            .into_iter()
+            .map(|(entry_type_name, entry_content)| CommitEntryArgs {
+                entry_type_name: entry_type_name.into(),
+                entry_value: entry_content.to_string(),
+            })
+            .collect(),
+    };
+    let result: CommitEntriesResult = host_call!(call_ribosome(hc_commit_entries, input))?;
+    Ok(result.results.into_iter().map(commit_entry_result).collect())
+}
 
-    // Free result & input allocations and all allocations made inside commit()
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("deallocate failed");
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn commit_entries<S: Into<String>>(
+    _entries: Vec<(S, serde_json::Value)>,
+) -> Result<Vec<Result<HashString, RibosomeError>>, RibosomeError> {
+    // FIXME
+    Err(RibosomeError::FunctionNotImplemented)
+}
 
+fn commit_entry_result(output: CommitEntryResult) -> Result<HashString, RibosomeError> {
     if output.validation_failure.len() > 0 {
-        Err(RibosomeError::ValidationFailed(output.validation_failure))
+        Err(RibosomeError::ValidationFailed(
+            validation::parse_validation_failure(&output.validation_failure),
+        ))
     } else {
         Ok(HashString::from(output.address))
     }
@@ -328,46 +607,79 @@ pub fn remove_entry<S: Into<String>>(
 }
 
 /// implements access to low-level WASM hc_get_entry
+/// System entries can be read too, e.g. at the addresses returned by
+/// `query(SystemEntryType::Deletion.into(), 0)`.
+/// The ribosome does not report the type of the entry, see `get_entry_of_type`.
 pub fn get_entry(entry_hash: HashString) -> Result<Option<String>, RibosomeError> {
-    let mut mem_stack: SinglePageStack;
-    unsafe {
-        mem_stack = G_MEM_STACK.unwrap();
-    }
-
     // Put args in struct and serialize into memory
     let input = GetEntryArgs {
         address: entry_hash,
     };
-    let maybe_allocation_of_input = serialize(&mut mem_stack, input);
-    if let Err(err_code) = maybe_allocation_of_input {
-        return Err(RibosomeError::RibosomeFailed(err_code.to_string()));
+    let result: GetEntryResult = host_call!(call_ribosome(hc_get_entry, input))?;
+    Ok(get_entry_result(result))
+}
+
+/// Same as `get_entry`, but only returns the entry if it is of type `entry_type`,
+/// e.g. `get_entry_of_type(address, SystemEntryType::Agent.into())`.
+/// The type is read from the chain header of the entry, so only entries
+/// of the local chain are found: `None` is returned for the others.
+/// As `get_header`, it fails with `FunctionNotImplemented` in a zome for now.
+pub fn get_entry_of_type(
+    entry_hash: HashString,
+    entry_type: EntryType,
+) -> Result<Option<String>, RibosomeError> {
+    match get_header(entry_hash.clone())? {
+        Some(ref header) if header.entry_type == entry_type => get_entry(entry_hash),
+        _ => Ok(None),
     }
-    let allocation_of_input = maybe_allocation_of_input.unwrap();
+}
 
-    // Call WASMI-able get_entry
-    let encoded_allocation_of_result: u32;
-    unsafe {
-        encoded_allocation_of_result = hc_get_entry(allocation_of_input.encode() as u32);
-    }
-    // Deserialize complex result stored in memory and check for ERROR in encoding
-    let result = try_deserialize_allocation(encoded_allocation_of_result as u32);
-    if let Err(err_str) = result {
-        return Err(RibosomeError::RibosomeFailed(err_str));
-    }
-    let result : GetEntryResult = result.unwrap();
+/// Gets several entries, passing their addresses to the ribosome in a single call.
+/// Returns each entry, or `None` if not found, in the order of `addresses`.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn get_entries(addresses: Vec<HashString>) -> Result<Vec<Option<String>>, RibosomeError> {
+    let input = GetEntriesArgs { addresses };
+    let result: GetEntriesResult = host_call!(call_ribosome(hc_get_entries, input))?;
+    Ok(result.results.into_iter().map(get_entry_result).collect())
+}
 
-    // Free result & input allocations and all allocations made inside commit()
-    mem_stack
-        .deallocate(allocation_of_input)
-        .expect("deallocate failed");
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn get_entries(_addresses: Vec<HashString>) -> Result<Vec<Option<String>>, RibosomeError> {
+    // FIXME
+    Err(RibosomeError::FunctionNotImplemented)
+}
 
+fn get_entry_result(result: GetEntryResult) -> Option<String> {
     match result.status {
-        GetResultStatus::Found => Ok(Some(result.entry)),
-        GetResultStatus::NotFound => Ok(None),
+        GetResultStatus::Found => Some(result.entry),
+        GetResultStatus::NotFound => None,
     }
 }
 
+/// Links the entry at `target` from the entry at `base` with `tag`,
+/// so it can be found with `get_links(base, tag)`.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn link_entries<S: Into<String>>(
+    base: HashString,
+    target: HashString,
+    tag: S
+) -> Result<(), RibosomeError> {
+    let input = LinkEntriesArgs {
+        base,
+        target,
+        tag: tag.into(),
+    };
+    let _: serde_json::Value = host_call!(call_ribosome(hc_link_entries, input))?;
+    Ok(())
+}
+
 /// FIXME DOC
+#[cfg(not(feature = "mock"))]
 pub fn link_entries<S: Into<String>>(
     _base: HashString,
     _target: HashString,
@@ -377,22 +689,186 @@ pub fn link_entries<S: Into<String>>(
     Err(RibosomeError::FunctionNotImplemented)
 }
 
-/// FIXME DOC
+/// Returns the addresses of all the entries linked from `base` with `tag`,
+/// getting every page of links the ribosome splits them into.
+/// Use `get_links_with_options` to get them one page at a time.
+/// As `get_links_with_options`, it fails with `FunctionNotImplemented` in a zome for now.
 pub fn get_links<S: Into<String>>(
+    base: HashString,
+    tag: S,
+) -> Result<Vec<HashString>, RibosomeError> {
+    let links = get_all_links(base, tag.into(), GetLinksOptions::default())?;
+    Ok(links.into_iter().map(|link| link.target).collect())
+}
+
+/// Returns a page of the links from `base` with `tag`, with the author, timestamp
+/// and status of each link.
+/// Pass the `next_page_token` of the result in `options.page_token` to get the next page.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn get_links_with_options<S: Into<String>>(
+    base: HashString,
+    tag: S,
+    options: GetLinksOptions,
+) -> Result<GetLinksResult, RibosomeError> {
+    let input = GetLinksArgs {
+        base,
+        tag: tag.into(),
+        options,
+    };
+    host_call!(call_ribosome(hc_get_links, input))
+}
+
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn get_links_with_options<S: Into<String>>(
     _base: HashString,
     _tag: S,
-) -> Result<Vec<HashString>, RibosomeError> {
+    _options: GetLinksOptions,
+) -> Result<GetLinksResult, RibosomeError> {
+    // FIXME
+    Err(RibosomeError::FunctionNotImplemented)
+}
+
+// Gets the links of every page, from the page of `options.page_token` on.
+// Fails on an empty or already seen page token, which would get the same pages forever.
+fn get_all_links(
+    base: HashString,
+    tag: String,
+    mut options: GetLinksOptions,
+) -> Result<Vec<Link>, RibosomeError> {
+    let mut links = Vec::new();
+    let mut page_tokens: Vec<String> = options.page_token.iter().cloned().collect();
+    loop {
+        let page = get_links_with_options(base.clone(), tag.clone(), options.clone())?;
+        links.extend(page.links);
+        match page.next_page_token {
+            Some(page_token) => {
+                if page_token.is_empty() || page_tokens.contains(&page_token) {
+                    return Err(RibosomeError::RibosomeFailed(format!(
+                        "invalid next page token of the links with tag {}: {:?}",
+                        tag, page_token
+                    )));
+                }
+                page_tokens.push(page_token.clone());
+                options.page_token = Some(page_token);
+            }
+            None => return Ok(links),
+        }
+    }
+}
+
+/// Returns all the entries linked from `base` with `tag`, parsed from JSON,
+/// loading them in the same calls to the ribosome as the links, page after page.
+/// Targets that could not be found are reported as `HashNotFound`, and targets that
+/// do not parse as a `T` as `RibosomeFailed`; use `.filter_map(Result::ok)` to skip them.
+/// As `get_links_with_options`, it fails with `FunctionNotImplemented` in a zome for now.
+pub fn get_links_and_load<T: serde::de::DeserializeOwned, S: Into<String>>(
+    base: HashString,
+    tag: S,
+) -> Result<Vec<Result<T, RibosomeError>>, RibosomeError> {
+    let options = GetLinksOptions {
+        load_entries: true,
+        ..Default::default()
+    };
+    let links = get_all_links(base, tag.into(), options)?;
+    Ok(links
+        .into_iter()
+        .map(|link| match link.entry {
+            Some(entry) => serde_json::from_str(&entry)
+                .map_err(|err| RibosomeError::RibosomeFailed(err.to_string())),
+            None => Err(RibosomeError::HashNotFound),
+        })
+        .collect())
+}
+
+/// Returns the addresses of the entries of the given type on the local chain,
+/// most recent first, at most `limit` of them (0 for all).
+/// System entries can be queried too, e.g. `query(SystemEntryType::Agent.into(), 1)`
+/// returns the address of the latest agent entry.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn query(entry_type: EntryType, limit: u32) -> Result<Vec<HashString>, RibosomeError> {
+    let input = QueryArgs {
+        entry_type,
+        limit,
+        headers: false,
+    };
+    let result: QueryResult = host_call!(call_ribosome(hc_query, input))?;
+    Ok(result.addresses)
+}
+
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn query(_entry_type: EntryType, _limit: u32) -> Result<Vec<HashString>, RibosomeError> {
+    // FIXME
+    Err(RibosomeError::FunctionNotImplemented)
+}
+
+/// Same as `query`, but returns the chain headers of the entries found,
+/// e.g. to sort them by creation time.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn query_headers(entry_type: EntryType, limit: u32) -> Result<Vec<ChainHeader>, RibosomeError> {
+    let input = QueryArgs {
+        entry_type,
+        limit,
+        headers: true,
+    };
+    let result: QueryResult = host_call!(call_ribosome(hc_query, input))?;
+    Ok(result.headers)
+}
+
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn query_headers(_entry_type: EntryType, _limit: u32) -> Result<Vec<ChainHeader>, RibosomeError> {
     // FIXME
     Err(RibosomeError::FunctionNotImplemented)
 }
 
+/// Returns the chain header of the entry at `entry_address`:
+/// its type, timestamp, signature and the previous header on the chain.
+/// Returns `None` if the entry is not on the local chain.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn get_header(entry_address: HashString) -> Result<Option<ChainHeader>, RibosomeError> {
+    let input = GetHeaderArgs { entry_address };
+    let result: GetHeaderResult = host_call!(call_ribosome(hc_get_header, input))?;
+    Ok(result.header)
+}
+
 /// FIXME DOC
-pub fn query() -> Result<Vec<String>, RibosomeError> {
+#[cfg(not(feature = "mock"))]
+pub fn get_header(_entry_address: HashString) -> Result<Option<ChainHeader>, RibosomeError> {
     // FIXME
     Err(RibosomeError::FunctionNotImplemented)
 }
 
+/// Sends `message` directly to the agent whose key hash is `to`,
+/// and returns the JSON response of their zome.
+/// Fails if the agent can not be reached or does not answer in time.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn send(
+    to: HashString,
+    message: serde_json::Value,
+) -> Result<serde_json::Value, RibosomeError> {
+    let input = SendArgs {
+        to,
+        message: message.to_string(),
+    };
+    let result: SendResult = host_call!(call_ribosome(hc_send, input))?;
+    serde_json::from_str(&result.response)
+        .map_err(|err| RibosomeError::RibosomeFailed(err.to_string()))
+}
+
 /// FIXME DOC
+#[cfg(not(feature = "mock"))]
 pub fn send(
     _to: HashString,
     _message: serde_json::Value,
@@ -410,3 +886,53 @@ pub fn start_bundle(_timeout: usize, _user_param: serde_json::Value) {
 pub fn close_bundle(_action: BundleOnClose) {
     // FIXME
 }
+
+/// Returns the bridges between this DNA and the other DNAs run by this agent.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn get_bridges() -> Result<Vec<Bridge>, RibosomeError> {
+    let result: GetBridgesResult = host_call!(call_ribosome(hc_get_bridges, ()))?;
+    Ok(result.bridges)
+}
+
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn get_bridges() -> Result<Vec<Bridge>, RibosomeError> {
+    // FIXME
+    Err(RibosomeError::FunctionNotImplemented)
+}
+
+/// Calls a zome function of the DNA on the other side of the bridge `bridge_name`
+/// and returns its JSON output.
+/// Only the mock host implements it for now: in a zome it fails with
+/// `FunctionNotImplemented`.
+#[cfg(feature = "mock")]
+pub fn bridge_call<S: Into<String>>(
+    bridge_name: S,
+    zome_name: S,
+    function_name: S,
+    arguments: serde_json::Value,
+) -> Result<serde_json::Value, RibosomeError> {
+    let input = BridgeCallArgs {
+        bridge_name: bridge_name.into(),
+        zome_name: zome_name.into(),
+        function_name: function_name.into(),
+        arguments: arguments.to_string(),
+    };
+    let result: BridgeCallResult = host_call!(call_ribosome(hc_bridge_call, input))?;
+    serde_json::from_str(&result.result)
+        .map_err(|err| RibosomeError::RibosomeFailed(err.to_string()))
+}
+
+/// FIXME DOC
+#[cfg(not(feature = "mock"))]
+pub fn bridge_call<S: Into<String>>(
+    _bridge_name: S,
+    _zome_name: S,
+    _function_name: S,
+    _arguments: serde_json::Value,
+) -> Result<serde_json::Value, RibosomeError> {
+    // FIXME
+    Err(RibosomeError::FunctionNotImplemented)
+}
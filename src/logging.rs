@@ -0,0 +1,187 @@
+//! File holding the leveled, structured logging of the zome, used through the
+//! `hdk_error!`, `hdk_warn!`, `hdk_info!`, `hdk_debug!` and `hdk_trace!` macros.
+//!
+//! Each record is sent to the host through `debug` as a JSON object holding its level,
+//! the zome and zome function it was logged from, its message and its key-value fields:
+//! `{"level":"info","zome":"blog","function":"create_post","message":"..","fields":{..}}`
+//!
+//! Records more verbose than the level set with one of the `max_level_*` features,
+//! e.g. `max_level_info` in release builds, are stripped at compile time.
+//!
+//! Records of crates logging through the `log` crate are forwarded the same way,
+//! with their target as a field, once `install` has run. The functions and callbacks
+//! generated by the zome macros run it when they start.
+
+use log;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fmt;
+use try_globals;
+use zome_call::current_function;
+
+/// Level of a log record, from the most to the least severe
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error = 1,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Trace => "trace",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl From<log::Level> for Level {
+    fn from(level: log::Level) -> Level {
+        match level {
+            log::Level::Error => Level::Error,
+            log::Level::Warn => Level::Warn,
+            log::Level::Info => Level::Info,
+            log::Level::Debug => Level::Debug,
+            log::Level::Trace => Level::Trace,
+        }
+    }
+}
+
+/// The most verbose level logged, or `Off` to log nothing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LevelFilter {
+    Off,
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+/// The most verbose level logged, set at compile time with the `max_level_*` features
+#[cfg(feature = "max_level_off")]
+pub const STATIC_MAX_LEVEL: LevelFilter = LevelFilter::Off;
+#[cfg(all(not(feature = "max_level_off"), feature = "max_level_error"))]
+pub const STATIC_MAX_LEVEL: LevelFilter = LevelFilter::Error;
+#[cfg(all(
+    not(any(feature = "max_level_off", feature = "max_level_error")),
+    feature = "max_level_warn"
+))]
+pub const STATIC_MAX_LEVEL: LevelFilter = LevelFilter::Warn;
+#[cfg(all(
+    not(any(
+        feature = "max_level_off",
+        feature = "max_level_error",
+        feature = "max_level_warn"
+    )),
+    feature = "max_level_info"
+))]
+pub const STATIC_MAX_LEVEL: LevelFilter = LevelFilter::Info;
+#[cfg(all(
+    not(any(
+        feature = "max_level_off",
+        feature = "max_level_error",
+        feature = "max_level_warn",
+        feature = "max_level_info"
+    )),
+    feature = "max_level_debug"
+))]
+pub const STATIC_MAX_LEVEL: LevelFilter = LevelFilter::Debug;
+#[cfg(not(any(
+    feature = "max_level_off",
+    feature = "max_level_error",
+    feature = "max_level_warn",
+    feature = "max_level_info",
+    feature = "max_level_debug"
+)))]
+pub const STATIC_MAX_LEVEL: LevelFilter = LevelFilter::Trace;
+
+impl From<LevelFilter> for log::LevelFilter {
+    fn from(filter: LevelFilter) -> log::LevelFilter {
+        match filter {
+            LevelFilter::Off => log::LevelFilter::Off,
+            LevelFilter::Error => log::LevelFilter::Error,
+            LevelFilter::Warn => log::LevelFilter::Warn,
+            LevelFilter::Info => log::LevelFilter::Info,
+            LevelFilter::Debug => log::LevelFilter::Debug,
+            LevelFilter::Trace => log::LevelFilter::Trace,
+        }
+    }
+}
+
+/// Whether records of `level` are logged.
+/// The macros check it before building the record, so disabled records cost nothing.
+#[inline(always)]
+pub fn enabled(level: Level) -> bool {
+    level as usize <= STATIC_MAX_LEVEL as usize
+}
+
+/// Converts the value of a field to JSON, used by the logging macros
+#[doc(hidden)]
+pub fn field<T: Serialize + ?Sized>(value: &T) -> Value {
+    ::serde_json::to_value(value).unwrap_or_else(|err| Value::String(err.to_string()))
+}
+
+/// Sends a record to the host, used by the logging macros.
+/// Logging never fails the zome function: a record the host refuses is dropped.
+#[doc(hidden)]
+pub fn log(level: Level, message: String, fields: Vec<(&str, Value)>) {
+    let _ = ::debug(&record_json(level, message, fields).to_string());
+}
+
+/// JSON of a log record, with the zome and function it was logged from
+pub(crate) fn record_json(level: Level, message: String, fields: Vec<(&str, Value)>) -> Value {
+    let zome = try_globals()
+        .ok()
+        .and_then(|app_globals| app_globals.zome_name.clone());
+    let fields: Map<String, Value> = fields
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value))
+        .collect();
+    json!({
+        "level": level,
+        "zome": zome,
+        "function": current_function(),
+        "message": message,
+        "fields": fields,
+    })
+}
+
+/// The `log::Log` implementation forwarding the records of the `log` crate to the host
+pub struct HdkLogger;
+
+static LOGGER: HdkLogger = HdkLogger;
+
+impl log::Log for HdkLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        enabled(metadata.level().into())
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            log(
+                record.level().into(),
+                record.args().to_string(),
+                vec![("target", Value::String(record.target().to_string()))],
+            );
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Installs `HdkLogger` as the logger of the `log` crate, up to `STATIC_MAX_LEVEL`.
+/// Does nothing if a logger is already installed, so it can be called on every zome call.
+pub fn install() {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(STATIC_MAX_LEVEL.into());
+    }
+}
@@ -2,7 +2,7 @@
 macro_rules! try_deserialize_arguments {
     ($encoded_allocation_of_input:ident) => (
         {
-            let maybe_input = ::holochain_wasm_utils::memory_serialization::try_deserialize_allocation($encoded_allocation_of_input);
+            let maybe_input = ::hdk::deserialize_wasm_input($encoded_allocation_of_input);
             if let Err(_) = maybe_input {
                 return ::holochain_wasm_utils::error::RibosomeErrorCode::ArgumentDeserializationFailed as u32;
             }
@@ -14,6 +14,14 @@ macro_rules! try_deserialize_arguments {
 
 /// A macro for easily writing zome functions
 ///
+/// # Panics
+///
+/// A panic in a zome function is reported to the host through `debug`, as an error log
+/// record holding the panic message and location. Natively, e.g. with the mock host,
+/// the function then returns the `ZomeFunctionPanicked` error as its output.
+/// WASM zomes can not catch panics: the wasm32 target only supports `panic = "abort"`,
+/// so the call still traps after the report.
+///
 /// # Examples
 /// ```
 /// # #[macro_use] extern crate hdk;
@@ -50,83 +58,620 @@ macro_rules! zome_functions {
             #[no_mangle]
             pub extern "C" fn $func_name(encoded_allocation_of_input: u32) -> u32 {
 
+                // Report panics to the host instead of trapping without a message
+                ::hdk::zome_call::install_panic_hook();
+                ::hdk::zome_call::catch_panic(|| {
+                    ::hdk::init_memory_stack(encoded_allocation_of_input);
+                    let zome_call = ::hdk::zome_call::enter(stringify!($func_name), encoded_allocation_of_input);
+
+                    // Refuse to run against a ribosome implementing another zome API version
+                    if let Err(err) = ::hdk::check_api_version() {
+                        let output_obj = err.to_json();
+                        zome_call.exit(&output_obj);
+                        return ::hdk::serialize_wasm_output(output_obj);
+                    }
+
+                    // Macro'd InputStruct
+                    #[derive(Deserialize)]
+                    struct InputStruct {
+                        $($param : $param_type),*
+                    }
+
+                    // Deserialize input
+                    let maybe_input = try_deserialize_arguments!(encoded_allocation_of_input);
+                    let input: InputStruct = maybe_input.unwrap();
+
+                    // Macro'd function body
+                    fn execute(params: InputStruct) -> impl ::serde::Serialize {
+                        let InputStruct { $($param),* } = params;
+                        $main_block
+                    }
+
+                    // Execute inner function
+                    let output_obj = execute(input);
+                    zome_call.exit(&output_obj);
+
+                    ::hdk::serialize_wasm_output(output_obj)
+                }, |err| ::hdk::serialize_wasm_output(err.to_json()))
+            }
+        )+
+    );
+}
+
+
+/// A macro for declaring the validation callbacks of a zome
+///
+/// Each block generates an exported validation function. The block kind selects
+/// which operation is being validated and which arguments the callback receives:
+/// - `[ENTRY]` the entry being committed
+/// - `[UPDATE]` the entry being replaced and its new version
+/// - `[DELETE]` the entry being removed
+/// - `[LINK]` the base, target and tag of the link, and whether it is added or deleted
+///
+/// Every callback also receives the `ValidationData` provided by the host,
+/// with the `ValidationContext` helpers (`author()`, `is_signed_by()`, `author_in()`) in scope.
+/// For `[ENTRY]` and `[UPDATE]` blocks, the `#[validate(...)]` field rules of entry types
+/// deriving `Validate` (see the `hdk_derive` crate) are checked before the body runs.
+/// A callback returns `Ok(())` to accept, or `Err` with a message to reject.
+/// To reject with a `ValidationError`, or a `Vec<ValidationError>` to report several failures
+/// at once, name the error type after the arguments: `|..| -> hdk::ValidationError { .. }`.
+/// Errors reach `commit_entry` callers as `RibosomeError::ValidationFailed`.
+///
+/// Panics are reported as in `zome_functions!`. Natively the callback then rejects with
+/// the message of the `ZomeFunctionPanicked` error, while in WASM the call traps.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate hdk;
+/// # extern crate holochain_wasm_utils;
+/// # extern crate serde;
+/// # extern crate serde_json;
+/// # #[macro_use] extern crate serde_derive;
+/// # use holochain_wasm_utils::holochain_core_types::hash::HashString;
+/// # fn main() {
+/// #[derive(Deserialize)]
+/// struct Post {
+///     content: String,
+/// }
+///
+/// validations! {
+///     [ENTRY] validate_post {
+///         [hdk::ValidationPackage::Entry]
+///         |post: Post, _ctx: hdk::ValidationData| -> hdk::ValidationError {
+///             if post.content.is_empty() {
+///                 Err(hdk::ValidationError::new("required", "content is empty").with_field("content"))
+///             } else {
+///                 Ok(())
+///             }
+///         }
+///     }
+///
+///     [DELETE] validate_delete_post {
+///         [hdk::ValidationPackage::Entry]
+///         |_old_post: Post, _ctx: hdk::ValidationData| {
+///             Err("posts can not be deleted".to_string())
+///         }
+///     }
+///
+///     [LINK] validate_link_post {
+///         [hdk::ValidationPackage::Entry]
+///         |_base: HashString, _target: HashString, tag: String, _action: hdk::LinkAction, _ctx: hdk::ValidationData| {
+///             if tag == "comments" {
+///                 Ok(())
+///             } else {
+///                 Err(format!("unknown tag: {}", tag))
+///             }
+///         }
+///     }
+/// }
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! validations {
+    () => ();
+
+    (
+        [ENTRY] $func_name:ident {
+            [$package:path]
+            | $entry:ident : $entry_type:ty, $ctx:ident : hdk::ValidationData | $($body:tt)+
+        }
+        $($rest:tt)*
+    ) => (
+        validation_callback! {
+            $func_name,
+            [$entry : $entry_type => entry, $ctx : ::hdk::ValidationData => ctx],
+            [$entry],
+            $($body)+
+        }
+
+        validations!($($rest)*);
+    );
+
+    (
+        [UPDATE] $func_name:ident {
+            [$package:path]
+            | $old_entry:ident : $old_entry_type:ty, $new_entry:ident : $new_entry_type:ty, $ctx:ident : hdk::ValidationData | $($body:tt)+
+        }
+        $($rest:tt)*
+    ) => (
+        validation_callback! {
+            $func_name,
+            [
+                $old_entry : $old_entry_type => old_entry,
+                $new_entry : $new_entry_type => new_entry,
+                $ctx : ::hdk::ValidationData => ctx
+            ],
+            [$new_entry],
+            $($body)+
+        }
+
+        validations!($($rest)*);
+    );
+
+    (
+        [DELETE] $func_name:ident {
+            [$package:path]
+            | $old_entry:ident : $old_entry_type:ty, $ctx:ident : hdk::ValidationData | $($body:tt)+
+        }
+        $($rest:tt)*
+    ) => (
+        validation_callback! {
+            $func_name,
+            [$old_entry : $old_entry_type => old_entry, $ctx : ::hdk::ValidationData => ctx],
+            [],
+            $($body)+
+        }
+
+        validations!($($rest)*);
+    );
+
+    (
+        [LINK] $func_name:ident {
+            [$package:path]
+            | $base:ident : $base_type:ty, $target:ident : $target_type:ty, $tag:ident : $tag_type:ty,
+              $action:ident : hdk::LinkAction, $ctx:ident : hdk::ValidationData | $($body:tt)+
+        }
+        $($rest:tt)*
+    ) => (
+        validation_callback! {
+            $func_name,
+            [
+                $base : $base_type => base,
+                $target : $target_type => target,
+                $tag : $tag_type => tag,
+                $action : ::hdk::LinkAction => action,
+                $ctx : ::hdk::ValidationData => ctx
+            ],
+            [],
+            $($body)+
+        }
+
+        validations!($($rest)*);
+    );
+}
+
+/// Generates one validation callback of `validations!`
+///
+/// Each argument is given with the name of its field in the JSON sent by the host,
+/// followed by the arguments whose field rules are checked before the body runs,
+/// and by the body with the error type it returns, `String` when it is not named.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! validation_callback {
+    (
+        $func_name:ident,
+        [$($param:ident : $param_type:ty => $field:ident),+],
+        [$($checked:ident),*],
+        -> $error_type:ty $main_block:block
+    ) => (
+        #[no_mangle]
+        pub extern "C" fn $func_name(encoded_allocation_of_input: u32) -> u32 {
+
+            mark_callback!($func_name);
+
+            // Report panics to the host instead of trapping without a message
+            ::hdk::zome_call::install_panic_hook();
+            ::hdk::zome_call::catch_panic(|| {
                 ::hdk::init_memory_stack(encoded_allocation_of_input);
+                let zome_call = ::hdk::zome_call::enter_callback(stringify!($func_name), encoded_allocation_of_input);
+
+                // Refuse to run against a ribosome implementing another zome API version
+                if let Err(err) = ::hdk::check_api_version() {
+                    let errors = ::hdk::IntoValidationErrors::into_validation_errors(err.to_string());
+                    zome_call.exit(&errors);
+                    return ::hdk::serialize_wasm_output(errors);
+                }
 
                 // Macro'd InputStruct
                 #[derive(Deserialize)]
                 struct InputStruct {
-                    $($param : $param_type),*
+                    $($param : $param_type),+
+                }
+
+                #[derive(Deserialize)]
+                struct InputStructGeneric {
+                    $($field : $param_type),+
                 }
 
                 // Deserialize input
                 let maybe_input = try_deserialize_arguments!(encoded_allocation_of_input);
-                let input: InputStruct = maybe_input.unwrap();
+                let input_generic: InputStructGeneric = maybe_input.unwrap();
+                let input = InputStruct {
+                    $($param: input_generic.$field),+
+                };
+
+                // Run the field rules of entry types deriving `Validate`
+                #[allow(unused_imports)]
+                use ::hdk::validation::{CheckFieldRules, SkipFieldRules};
+                $(
+                    if let Err(errors) = (&::hdk::validation::FieldRules(&input.$checked)).check() {
+                        zome_call.exit(&errors);
+                        return ::hdk::serialize_wasm_output(errors);
+                    }
+                )*
 
                 // Macro'd function body
-                fn execute(params: InputStruct) -> impl ::serde::Serialize {
-                    let InputStruct { $($param),* } = params;
+                fn execute(params: InputStruct) -> Result<(), $error_type> {
+                    #[allow(unused_imports)]
+                    use ::hdk::ValidationContext;
+                    let InputStruct { $($param),+ } = params;
                     $main_block
                 }
 
                 // Execute inner function
-                let output_obj = execute(input);
+                let validation_result = execute(input);
+                match validation_result {
+                    Ok(()) => {
+                        zome_call.exit(&());
+                        0
+                    }
+                    Err(failure) => {
+                        let errors = ::hdk::IntoValidationErrors::into_validation_errors(failure);
+                        zome_call.exit(&errors);
+                        ::hdk::serialize_wasm_output(errors)
+                    }
+                }
+            }, |err| ::hdk::serialize_wasm_output(
+                ::hdk::IntoValidationErrors::into_validation_errors(err.to_string())
+            ))
+        }
+    );
 
-                ::hdk::serialize_wasm_output(output_obj)
-            }
-        )+
+    // Callbacks not naming their error type reject with a message
+    (
+        $func_name:ident,
+        [$($param:ident : $param_type:ty => $field:ident),+],
+        [$($checked:ident),*],
+        $main_block:expr
+    ) => (
+        validation_callback! {
+            $func_name,
+            [$($param : $param_type => $field),+],
+            [$($checked),*],
+            -> String { $main_block }
+        }
     );
 }
 
+/// A macro for writing the genesis callback of a zome
+///
+/// The body runs once when the zome starts and can commit the initial entries and links
+/// of the zome. Returning an error aborts the startup of the zome with that message.
+///
+/// Panics are reported as in `zome_functions!`. Natively the callback then fails with
+/// the message of the `ZomeFunctionPanicked` error, while in WASM the call traps.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate hdk;
+/// # extern crate holochain_wasm_utils;
+/// # extern crate serde;
+/// # #[macro_use] extern crate serde_json;
+/// # #[macro_use] extern crate serde_derive;
+/// # fn main() {
+/// genesis! {
+///     || {
+///         hdk::commit_entry("handle", json!({ "name": "alex" }))
+///             .map(|_| ())
+///             .map_err(|err| format!("could not commit handle: {:?}", err))
+///     }
+/// }
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! genesis {
+    (
+        || $main_block:expr
+    ) => (
+        #[no_mangle]
+        pub extern "C" fn genesis(encoded_allocation_of_input: u32) -> u32 {
+
+            mark_callback!(genesis);
 
+            // Report panics to the host instead of trapping without a message
+            ::hdk::zome_call::install_panic_hook();
+            ::hdk::zome_call::catch_panic(|| {
+                ::hdk::init_memory_stack(encoded_allocation_of_input);
+                let zome_call = ::hdk::zome_call::enter_callback(stringify!(genesis), encoded_allocation_of_input);
+
+                // Refuse to run against a ribosome implementing another zome API version
+                if let Err(err) = ::hdk::check_api_version() {
+                    let fail_string = err.to_string();
+                    zome_call.exit(&fail_string);
+                    return ::hdk::serialize_wasm_output(fail_string);
+                }
+
+                // Macro'd function body
+                fn execute() -> Result<(), String> {
+                    $main_block
+                }
+
+                // Execute inner function
+                match execute() {
+                    Ok(()) => {
+                        zome_call.exit(&());
+                        0
+                    }
+                    Err(fail_string) => {
+                        zome_call.exit(&fail_string);
+                        ::hdk::serialize_wasm_output(fail_string)
+                    }
+                }
+            }, |err| ::hdk::serialize_wasm_output(err.to_string()))
+        }
+    );
+}
+
+/// A macro for writing the bridge genesis callback of a zome
+///
+/// The body runs when a bridge between this DNA and another DNA is created,
+/// with the side of the bridge this DNA is on and the hash of the other DNA.
+/// Returning an error refuses the bridge with that message.
+///
+/// Panics are reported as in `zome_functions!`. Natively the callback then refuses with
+/// the message of the `ZomeFunctionPanicked` error, while in WASM the call traps.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate hdk;
+/// # extern crate holochain_wasm_utils;
+/// # extern crate serde;
+/// # #[macro_use] extern crate serde_derive;
+/// # use holochain_wasm_utils::holochain_core_types::hash::HashString;
+/// # fn main() {
+/// bridge_genesis! {
+///     |side: hdk::BridgeSide, dna_hash: HashString| {
+///         hdk::debug(&format!("bridged {:?} {}", side, dna_hash))
+///             .map_err(|err| err.to_string())
+///     }
+/// }
+/// # }
+/// ```
+///
 #[macro_export]
-macro_rules! validations {
+macro_rules! bridge_genesis {
     (
-        $([ENTRY] $func_name:ident {
-            [$package:path]
-            | $entry:ident : $entry_type:ty, $ctx:ident : hdk::ValidationData | $main_block:expr
-        })+
+        | $side:ident : hdk::BridgeSide, $dna_hash:ident : $dna_hash_type:ty | $main_block:expr
     ) => (
+        #[no_mangle]
+        pub extern "C" fn bridge_genesis(encoded_allocation_of_input: u32) -> u32 {
 
-        $(
-            #[no_mangle]
-            pub extern "C" fn $func_name(encoded_allocation_of_input: u32) -> u32 {
+            mark_callback!(bridge_genesis);
 
+            // Report panics to the host instead of trapping without a message
+            ::hdk::zome_call::install_panic_hook();
+            ::hdk::zome_call::catch_panic(|| {
                 ::hdk::init_memory_stack(encoded_allocation_of_input);
+                let zome_call = ::hdk::zome_call::enter_callback(stringify!(bridge_genesis), encoded_allocation_of_input);
+
+                // Refuse to run against a ribosome implementing another zome API version
+                if let Err(err) = ::hdk::check_api_version() {
+                    let fail_string = err.to_string();
+                    zome_call.exit(&fail_string);
+                    return ::hdk::serialize_wasm_output(fail_string);
+                }
 
                 // Macro'd InputStruct
                 #[derive(Deserialize)]
                 struct InputStruct {
-                    $entry : $entry_type,
-                    $ctx : ::hdk::ValidationData,
+                    $side : ::hdk::BridgeSide,
+                    $dna_hash : $dna_hash_type,
                 }
 
                 #[derive(Deserialize)]
                 struct InputStructGeneric {
-                    entry : $entry_type,
-                    ctx : ::hdk::ValidationData,
+                    side : ::hdk::BridgeSide,
+                    dna_hash : $dna_hash_type,
                 }
 
                 // Deserialize input
                 let maybe_input = try_deserialize_arguments!(encoded_allocation_of_input);
                 let input_generic: InputStructGeneric = maybe_input.unwrap();
                 let input = InputStruct {
-                    $entry: input_generic.entry,
-                    $ctx: input_generic.ctx,
+                    $side: input_generic.side,
+                    $dna_hash: input_generic.dna_hash,
                 };
 
                 // Macro'd function body
                 fn execute(params: InputStruct) -> Result<(), String> {
-                    let InputStruct { $entry, $ctx } = params;
+                    let InputStruct { $side, $dna_hash } = params;
                     $main_block
                 }
 
                 // Execute inner function
-                let validation_result = execute(input);
-                match validation_result {
-                    Ok(()) => 0,
-                    Err(fail_string) => ::hdk::serialize_wasm_output(fail_string),
+                match execute(input) {
+                    Ok(()) => {
+                        zome_call.exit(&());
+                        0
+                    }
+                    Err(fail_string) => {
+                        zome_call.exit(&fail_string);
+                        ::hdk::serialize_wasm_output(fail_string)
+                    }
                 }
-            }
-        )+
+            }, |err| ::hdk::serialize_wasm_output(err.to_string()))
+        }
     );
 }
+
+/// A macro for writing the receive callback of a zome
+///
+/// The body runs when another agent sends a message to this agent with `hdk::send`,
+/// with the key hash of the sender and the message.
+/// Its output is serialized to JSON and returned to the sender as the response.
+///
+/// Panics are reported as in `zome_functions!`. Natively the callback then responds with
+/// the `ZomeFunctionPanicked` error, while in WASM the call traps.
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate hdk;
+/// # extern crate holochain_wasm_utils;
+/// # extern crate serde;
+/// # #[macro_use] extern crate serde_json;
+/// # #[macro_use] extern crate serde_derive;
+/// # use holochain_wasm_utils::holochain_core_types::hash::HashString;
+/// # fn main() {
+/// receive! {
+///     |from: HashString, message: String| {
+///         json!({ "echo": message, "from": from })
+///     }
+/// }
+/// # }
+/// ```
+///
+#[macro_export]
+macro_rules! receive {
+    (
+        | $from:ident : $from_type:ty, $message:ident : $message_type:ty | $main_block:expr
+    ) => (
+        #[no_mangle]
+        pub extern "C" fn receive(encoded_allocation_of_input: u32) -> u32 {
+
+            mark_callback!(receive);
+
+            // Report panics to the host instead of trapping without a message
+            ::hdk::zome_call::install_panic_hook();
+            ::hdk::zome_call::catch_panic(|| {
+                ::hdk::init_memory_stack(encoded_allocation_of_input);
+                let zome_call = ::hdk::zome_call::enter_callback(stringify!(receive), encoded_allocation_of_input);
+
+                // Refuse to run against a ribosome implementing another zome API version
+                if let Err(err) = ::hdk::check_api_version() {
+                    let output_obj = err.to_json();
+                    zome_call.exit(&output_obj);
+                    return ::hdk::serialize_wasm_output(output_obj);
+                }
+
+                // Macro'd InputStruct
+                #[derive(Deserialize)]
+                struct InputStruct {
+                    $from : $from_type,
+                    $message : $message_type,
+                }
+
+                #[derive(Deserialize)]
+                struct InputStructGeneric {
+                    from : $from_type,
+                    message : $message_type,
+                }
+
+                // Deserialize input
+                let maybe_input = try_deserialize_arguments!(encoded_allocation_of_input);
+                let input_generic: InputStructGeneric = maybe_input.unwrap();
+                let input = InputStruct {
+                    $from: input_generic.from,
+                    $message: input_generic.message,
+                };
+
+                // Macro'd function body
+                fn execute(params: InputStruct) -> impl ::serde::Serialize {
+                    let InputStruct { $from, $message } = params;
+                    $main_block
+                }
+
+                // Execute inner function
+                let output_obj = execute(input);
+                zome_call.exit(&output_obj);
+
+                ::hdk::serialize_wasm_output(output_obj)
+            }, |err| ::hdk::serialize_wasm_output(err.to_json()))
+        }
+    );
+}
+
+/// Places the marker of the callback `$func_name` in the data of the zome,
+/// see `hdk::zome_call::CallbackMarker`
+#[doc(hidden)]
+#[macro_export]
+macro_rules! mark_callback {
+    ($func_name:ident) => (
+        static CALLBACK_MARKER: ::hdk::zome_call::CallbackMarker = ::hdk::zome_call::CallbackMarker {
+            magic: ::hdk::zome_call::CALLBACK_MARKER_MAGIC,
+            callback: $func_name,
+        };
+        ::hdk::zome_call::keep_marker(&CALLBACK_MARKER);
+    );
+}
+
+/// Logs a record at the given `hdk::logging::Level`, used by the leveled logging macros
+#[doc(hidden)]
+#[macro_export]
+macro_rules! hdk_log {
+    ($level:expr, $($key:ident = $value:expr),+ ; $($arg:tt)+) => (
+        if ::hdk::logging::enabled($level) {
+            ::hdk::logging::log(
+                $level,
+                format!($($arg)+),
+                vec![$((stringify!($key), ::hdk::logging::field(&$value))),+],
+            );
+        }
+    );
+    ($level:expr, $($arg:tt)+) => (
+        if ::hdk::logging::enabled($level) {
+            ::hdk::logging::log($level, format!($($arg)+), Vec::new());
+        }
+    );
+}
+
+/// Logs an error, with optional key-value fields before the message
+///
+/// # Examples
+/// ```
+/// # #[macro_use] extern crate hdk;
+/// # fn main() {
+/// # let address = "QmPost";
+/// hdk_error!(address = address, retries = 3; "could not load post");
+/// hdk_error!("could not load {} posts", 2);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! hdk_error {
+    ($($arg:tt)+) => (hdk_log!(::hdk::logging::Level::Error, $($arg)+));
+}
+
+/// Logs a warning, with optional key-value fields before the message
+#[macro_export]
+macro_rules! hdk_warn {
+    ($($arg:tt)+) => (hdk_log!(::hdk::logging::Level::Warn, $($arg)+));
+}
+
+/// Logs an informational message, with optional key-value fields before the message
+#[macro_export]
+macro_rules! hdk_info {
+    ($($arg:tt)+) => (hdk_log!(::hdk::logging::Level::Info, $($arg)+));
+}
+
+/// Logs a debugging message, with optional key-value fields before the message
+#[macro_export]
+macro_rules! hdk_debug {
+    ($($arg:tt)+) => (hdk_log!(::hdk::logging::Level::Debug, $($arg)+));
+}
+
+/// Logs a tracing message, with optional key-value fields before the message
+#[macro_export]
+macro_rules! hdk_trace {
+    ($($arg:tt)+) => (hdk_log!(::hdk::logging::Level::Trace, $($arg)+));
+}
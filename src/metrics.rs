@@ -0,0 +1,215 @@
+//! File holding the profiling of the `metrics` feature: for each call of a zome function,
+//! the number of calls to each host function with the bytes they read and wrote,
+//! and an estimate of the peak usage of the memory stack.
+//!
+//! The metrics are logged through `debug` when the zome function returns, one JSON object
+//! per call, e.g. `{"hdk_metrics":{"function":"create_post",..,"host_calls":{..}}}`.
+//! Natively, sizes are those of the JSON in the mock memory, stacked as the single page
+//! stack of a WASM zome would hold them.
+
+use serde_json::Value;
+use std::collections::BTreeMap;
+#[cfg(feature = "metrics")]
+use std::cell::RefCell;
+
+// Signature of the host functions imported from the ribosome
+pub(crate) type HostFunction = unsafe extern "C" fn(u32) -> u32;
+
+/// Key of the JSON object holding the metrics of a call in the debug log
+pub const METRICS_KEY: &str = "hdk_metrics";
+
+/// Metrics of the calls made to one host function
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct HostCallMetrics {
+    pub calls: usize,
+    /// Bytes of the serialized inputs
+    pub input_bytes: usize,
+    /// Bytes of the results written by the host
+    pub output_bytes: usize,
+}
+
+/// Metrics of one call of a zome function
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct ZomeCallMetrics {
+    pub zome: Option<String>,
+    pub function: String,
+    /// Bytes of the input of the zome function
+    pub input_bytes: usize,
+    /// Bytes of the serialized output of the zome function
+    pub output_bytes: usize,
+    /// Estimated highest top of the memory stack during the call, in bytes.
+    /// The stack is not read while the zome function runs: the estimate is the highest end
+    /// of the inputs and results of its host calls and of its output, so allocations
+    /// the zome function freed before calling the host or returning are missed.
+    pub peak_stack_bytes: usize,
+    /// Metrics per host function called, by name, e.g. `hc_commit_entry`
+    pub host_calls: BTreeMap<String, HostCallMetrics>,
+}
+
+impl ZomeCallMetrics {
+    /// Finds the metrics of a call in a line of the debug log, which may hold text before it
+    pub fn from_log_line(line: &str) -> Option<ZomeCallMetrics> {
+        let start = line.find(&format!("{{\"{}\"", METRICS_KEY))?;
+        let mut line: Value = ::serde_json::from_str(line[start..].trim()).ok()?;
+        ::serde_json::from_value(line[METRICS_KEY].take()).ok()
+    }
+
+    /// Number of calls made to all host functions
+    pub fn host_call_count(&self) -> usize {
+        self.host_calls.values().map(|metrics| metrics.calls).sum()
+    }
+}
+
+#[cfg(feature = "metrics")]
+thread_local! {
+    static METRICS: RefCell<Option<ZomeCallMetrics>> = RefCell::new(None);
+}
+
+/// Starts measuring a call of the zome function `function`, called by the zome macros
+#[cfg(feature = "metrics")]
+pub(crate) fn start(function: &str, encoded_allocation_of_input: u32) {
+    let input_bytes = allocation_bytes(encoded_allocation_of_input);
+    let metrics = ZomeCallMetrics {
+        function: function.to_string(),
+        input_bytes,
+        peak_stack_bytes: stack_base().unwrap_or(input_bytes),
+        ..Default::default()
+    };
+    METRICS.with(|current| *current.borrow_mut() = Some(metrics));
+}
+
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub(crate) fn start(_function: &str, _encoded_allocation_of_input: u32) {}
+
+/// Logs the metrics of the zome function returning `output`, called by the zome macros
+#[cfg(feature = "metrics")]
+pub(crate) fn finish<O: ::serde::Serialize>(output: &O) {
+    // Taken first so the call to debug reporting them is not measured
+    let metrics = METRICS.with(|current| current.borrow_mut().take());
+    if let Some(mut metrics) = metrics {
+        metrics.output_bytes = ::serde_json::to_string(output)
+            .map(|output| output.len())
+            .unwrap_or(0);
+        let base = stack_base().unwrap_or(metrics.input_bytes);
+        metrics.peak_stack_bytes = metrics.peak_stack_bytes.max(base + metrics.output_bytes);
+        metrics.zome = ::try_globals()
+            .ok()
+            .and_then(|app_globals| app_globals.zome_name.clone());
+        // A failure to log must not fail the zome function
+        let _ = ::debug(&json!({ METRICS_KEY: metrics }).to_string());
+    }
+}
+
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub(crate) fn finish<O: ::serde::Serialize>(_output: &O) {}
+
+/// Stops measuring the call of a zome function returning without output, or with its
+/// metrics already logged
+#[cfg(feature = "metrics")]
+pub(crate) fn discard() {
+    METRICS.with(|current| *current.borrow_mut() = None);
+}
+
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub(crate) fn discard() {}
+
+/// Calls the host function `ribosome_fn` named `function`,
+/// measuring the sizes of its input and result
+#[cfg(feature = "metrics")]
+pub(crate) unsafe fn call(
+    function: &'static str,
+    ribosome_fn: HostFunction,
+    encoded_allocation_of_input: u32,
+) -> u32 {
+    // The host may free its input, so it is measured before the call
+    let input_bytes = allocation_bytes(encoded_allocation_of_input);
+    let input_end = allocation_end(encoded_allocation_of_input);
+    let encoded_allocation_of_result = ribosome_fn(encoded_allocation_of_input);
+    METRICS.with(|current| {
+        if let Some(ref mut metrics) = *current.borrow_mut() {
+            let output_bytes = allocation_bytes(encoded_allocation_of_result);
+            let stack_top = result_end(input_end, encoded_allocation_of_result);
+            metrics.peak_stack_bytes = metrics.peak_stack_bytes.max(stack_top);
+            let host_call = metrics
+                .host_calls
+                .entry(function.to_string())
+                .or_insert_with(HostCallMetrics::default);
+            host_call.calls += 1;
+            host_call.input_bytes += input_bytes;
+            host_call.output_bytes += output_bytes;
+        }
+    });
+    encoded_allocation_of_result
+}
+
+#[cfg(not(feature = "metrics"))]
+#[inline(always)]
+pub(crate) unsafe fn call(
+    _function: &'static str,
+    ribosome_fn: HostFunction,
+    encoded_allocation_of_input: u32,
+) -> u32 {
+    ribosome_fn(encoded_allocation_of_input)
+}
+
+// Encoded allocations hold the offset of the allocation in their high bits and its length
+// in their low bits. An offset of 0 means there is no allocation, e.g. for error codes.
+#[cfg(all(feature = "metrics", not(feature = "mock")))]
+fn allocation_bytes(encoded_allocation: u32) -> usize {
+    if encoded_allocation >> 16 == 0 {
+        0
+    } else {
+        (encoded_allocation & 0xffff) as usize
+    }
+}
+
+// Top of the memory stack above this allocation
+#[cfg(all(feature = "metrics", not(feature = "mock")))]
+fn allocation_end(encoded_allocation: u32) -> usize {
+    match allocation_bytes(encoded_allocation) {
+        0 => stack_base().unwrap_or(0),
+        length => (encoded_allocation >> 16) as usize + length,
+    }
+}
+
+// The result of the host function is allocated above its input
+#[cfg(all(feature = "metrics", not(feature = "mock")))]
+fn result_end(input_end: usize, encoded_allocation_of_result: u32) -> usize {
+    input_end.max(allocation_end(encoded_allocation_of_result))
+}
+
+// Top of the memory stack when the zome function started
+#[cfg(all(feature = "metrics", not(feature = "mock")))]
+fn stack_base() -> Option<usize> {
+    unsafe { ::globals::G_MEM_STACK }.map(|mem_stack| mem_stack.top() as usize)
+}
+
+#[cfg(all(feature = "metrics", feature = "mock"))]
+fn allocation_bytes(encoded_allocation: u32) -> usize {
+    ::mock::memory::stored_len(encoded_allocation)
+}
+
+// The mock memory has no stack: values are stacked above the input of the zome function
+#[cfg(all(feature = "metrics", feature = "mock"))]
+fn allocation_end(encoded_allocation: u32) -> usize {
+    let zome_input_bytes = METRICS.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map_or(0, |metrics| metrics.input_bytes)
+    });
+    zome_input_bytes + allocation_bytes(encoded_allocation)
+}
+
+#[cfg(all(feature = "metrics", feature = "mock"))]
+fn result_end(input_end: usize, encoded_allocation_of_result: u32) -> usize {
+    input_end + allocation_bytes(encoded_allocation_of_result)
+}
+
+#[cfg(all(feature = "metrics", feature = "mock"))]
+fn stack_base() -> Option<usize> {
+    None
+}
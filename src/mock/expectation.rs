@@ -0,0 +1,142 @@
+//! File holding the expectations scripted on the mock host: canned results returned
+//! by a host function in place of the mock implementation, e.g. to test error paths.
+
+use super::host::with_host;
+use holochain_wasm_utils::api_serialization::{
+    commit::CommitEntryResult,
+    get_entry::{GetEntryResult, GetResultStatus},
+};
+use serde::Serialize;
+use serde_json::Value;
+use ValidationError;
+
+/// Error returned by the expectations of `times_out()`
+pub const TIMEOUT_ERROR: &str = "Timeout";
+
+/// A canned result for the calls to a host function
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Expectation {
+    function: &'static str,
+    input: Option<Value>,
+    /// Number of calls left to answer, `None` to answer every call
+    remaining: Option<usize>,
+    output: Result<Value, String>,
+}
+
+impl Expectation {
+    pub(crate) fn matches(&self, function: &str, input: &Value) -> bool {
+        self.function == function
+            && self
+                .input
+                .as_ref()
+                .map_or(true, |expected| expected == input)
+    }
+
+    /// Answers a call, and returns whether the expectation should still be kept
+    pub(crate) fn answer(&mut self) -> (Result<Value, String>, bool) {
+        match self.remaining {
+            Some(ref mut remaining) => {
+                *remaining -= 1;
+                (self.output.clone(), *remaining > 0)
+            }
+            None => (self.output.clone(), true),
+        }
+    }
+
+    /// Whether the expectation still waits for calls it was scripted for
+    pub(crate) fn is_pending(&self) -> bool {
+        self.remaining.is_some()
+    }
+
+    pub(crate) fn function(&self) -> &'static str {
+        self.function
+    }
+}
+
+/// Builds an expectation for the calls to a host function, see `hdk::mock::expect`.
+/// By default it answers the next call only; nothing is scripted until one of
+/// the `returns*` methods, or a shortcut like `not_found()`, is called.
+#[must_use = "the expectation is only scripted once its result is set, e.g. with `returns`"]
+#[derive(Debug, Clone)]
+pub struct ExpectationBuilder {
+    function: &'static str,
+    input: Option<Value>,
+    remaining: Option<usize>,
+}
+
+impl ExpectationBuilder {
+    pub(crate) fn new(function: &'static str) -> ExpectationBuilder {
+        ExpectationBuilder {
+            function,
+            input: None,
+            remaining: Some(1),
+        }
+    }
+
+    /// Only answers the calls made with this input, e.g. `GetEntryArgs` for `hc_get_entry`
+    pub fn with_input<I: Serialize>(mut self, input: I) -> ExpectationBuilder {
+        self.input =
+            Some(::serde_json::to_value(input).expect("expected input should serialize to JSON"));
+        self
+    }
+
+    /// Answers the next `count` calls
+    pub fn times(mut self, count: usize) -> ExpectationBuilder {
+        assert!(count > 0, "an expectation must answer at least one call");
+        self.remaining = Some(count);
+        self
+    }
+
+    /// Answers every call until the mock host is reset
+    pub fn always(mut self) -> ExpectationBuilder {
+        self.remaining = None;
+        self
+    }
+
+    /// Makes the host function return `output`, serialized as its JSON result
+    pub fn returns<O: Serialize>(self, output: O) {
+        let output =
+            ::serde_json::to_value(output).expect("expected output should serialize to JSON");
+        self.script(Ok(output))
+    }
+
+    /// Makes the host function fail with `error`,
+    /// which the zome API functions return as `RibosomeError::RibosomeFailed`
+    pub fn returns_err<S: Into<String>>(self, error: S) {
+        self.script(Err(error.into()))
+    }
+
+    /// Makes `hc_get_entry` report that the entry was not found
+    pub fn not_found(self) {
+        self.returns(GetEntryResult {
+            status: GetResultStatus::NotFound,
+            entry: String::new(),
+        })
+    }
+
+    /// Makes `hc_commit_entry` report that the entry failed validation with `errors`
+    pub fn fails_validation(self, errors: Vec<ValidationError>) {
+        let validation_failure =
+            ::serde_json::to_string(&errors).expect("validation errors should serialize to JSON");
+        self.returns(CommitEntryResult {
+            address: String::new(),
+            validation_failure,
+        })
+    }
+
+    /// Makes the host function fail as if it timed out, e.g. `hc_send`
+    /// when the receiving agent does not answer
+    pub fn times_out(self) {
+        self.returns_err(TIMEOUT_ERROR)
+    }
+
+    fn script(self, output: Result<Value, String>) {
+        let expectation = Expectation {
+            function: self.function,
+            input: self.input,
+            remaining: self.remaining,
+            output,
+        };
+        with_host(|host| host.expect(expectation));
+    }
+}
@@ -0,0 +1,685 @@
+//! File holding the mock host: an in-memory source chain, DHT and links
+//! answering the zome API functions in place of the ribosome
+
+use super::{expectation::Expectation, memory, network, RecordedHostCall};
+use api_serialization::*;
+use holochain_wasm_utils::{
+    api_serialization::{
+        commit::{CommitEntryArgs, CommitEntryResult},
+        get_entry::{GetEntryArgs, GetEntryResult, GetResultStatus},
+    },
+    holochain_core_types::hash::HashString,
+};
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    cell::RefCell,
+    collections::hash_map::DefaultHasher,
+    collections::{HashMap, VecDeque},
+    hash::{Hash, Hasher},
+    mem,
+};
+use {CallProvenance, EntryStatus, EntryType, LinkSortOrder, VERSION};
+
+thread_local! {
+    static HOST: RefCell<MockHost> = RefCell::new(MockHost::default());
+}
+
+/// Runs `f` with the mock host of the current thread
+pub(crate) fn with_host<R, F: FnOnce(&mut MockHost) -> R>(f: F) -> R {
+    HOST.with(|host| f(&mut host.borrow_mut()))
+}
+
+/// Makes `host` the mock host of the current thread, and returns the previous one
+pub(crate) fn replace_host(host: MockHost) -> MockHost {
+    HOST.with(|current| mem::replace(&mut *current.borrow_mut(), host))
+}
+
+/// An entry committed to the source chain of the mock agent
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockChainEntry {
+    pub header: ChainHeader,
+    pub entry: String,
+}
+
+/// A call the zome made to a host function, recorded by the mock host
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockCall {
+    /// Name of the host function, e.g. `hc_commit_entry`
+    pub function: &'static str,
+    /// JSON input of the call, `null` for host functions taking none
+    pub input: Value,
+    /// JSON result of the call, or the error the host function failed with
+    pub output: Result<Value, String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MockLink {
+    pub(crate) base: HashString,
+    pub(crate) tag: String,
+    pub(crate) link: Link,
+}
+
+/// An entry or link committed by the mock agent, not yet gossiped to the other agents
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Publication {
+    Entry {
+        address: HashString,
+        entry_type: EntryType,
+        content: String,
+    },
+    Link(MockLink),
+}
+
+/// In-memory stand-in for the ribosome, answering the zome API functions natively.
+/// Addresses are derived from the entry type and content, but are not real multihashes.
+#[derive(Debug, Clone)]
+pub struct MockHost {
+    globals: Map<String, Value>,
+    chain: Vec<MockChainEntry>,
+    dht: HashMap<HashString, (EntryType, String)>,
+    links: Vec<MockLink>,
+    bridges: Vec<Bridge>,
+    debug_log: Vec<String>,
+    clock: u64,
+    expectations: Vec<Expectation>,
+    calls: Vec<MockCall>,
+    published: Vec<Publication>,
+    replay: Option<Replay>,
+}
+
+/// Recorded host function results, fed back to the zome in order
+#[derive(Debug, Clone, Default)]
+struct Replay {
+    results: VecDeque<RecordedHostCall>,
+    position: usize,
+    divergence: Option<String>,
+}
+
+impl Default for MockHost {
+    fn default() -> MockHost {
+        let globals = json!({
+            "app_name": "mock_app",
+            "app_dna_hash": "QmMockDna",
+            "app_agent_id_str": "mock_agent",
+            "app_agent_key_hash": "QmMockAgentKey",
+            "app_agent_initial_hash": "QmMockAgentEntry",
+            "app_agent_latest_hash": "QmMockAgentEntry",
+            "api_version": VERSION,
+            "zome_name": "mock_zome",
+            "capability_name": "mock_cap",
+            "call_provenance": CallProvenance::Local,
+        });
+        MockHost {
+            globals: match globals {
+                Value::Object(globals) => globals,
+                _ => unreachable!(),
+            },
+            chain: Vec::new(),
+            dht: HashMap::new(),
+            links: Vec::new(),
+            bridges: Vec::new(),
+            debug_log: Vec::new(),
+            clock: 0,
+            expectations: Vec::new(),
+            calls: Vec::new(),
+            published: Vec::new(),
+            replay: None,
+        }
+    }
+}
+
+impl MockHost {
+    /// Sets a field of the globals returned to `init_globals`, e.g. `app_agent_id_str`
+    pub fn set_global<V: Serialize>(&mut self, name: &str, value: V) {
+        let value = ::serde_json::to_value(value).expect("global should serialize to JSON");
+        self.globals.insert(name.to_string(), value);
+    }
+
+    /// Removes a field from the globals, to test zomes against older ribosomes
+    pub fn remove_global(&mut self, name: &str) {
+        self.globals.remove(name);
+    }
+
+    /// The messages passed to `debug`, oldest first
+    pub fn debug_log(&self) -> &[String] {
+        &self.debug_log
+    }
+
+    /// The entries committed by the zome, oldest first
+    pub fn chain(&self) -> &[MockChainEntry] {
+        &self.chain
+    }
+
+    /// The content of the entry at `address`, wherever it was committed
+    pub fn entry(&self, address: &HashString) -> Option<&str> {
+        self.dht.get(address).map(|(_, content)| content.as_str())
+    }
+
+    /// Puts an entry on the DHT without committing it to the chain of the mock agent,
+    /// as if another agent had committed it
+    pub fn put_entry(&mut self, entry_type: EntryType, content: &str) -> HashString {
+        let address = mock_address(&entry_type.to_string(), content);
+        self.dht.insert(address.clone(), (entry_type, content.to_string()));
+        address
+    }
+
+    /// Adds a link as if the agent `author` had added it
+    pub fn add_link(
+        &mut self,
+        base: HashString,
+        target: HashString,
+        tag: &str,
+        author: HashString,
+    ) {
+        let timestamp = self.tick();
+        self.links.push(MockLink {
+            base,
+            tag: tag.to_string(),
+            link: Link {
+                target,
+                author,
+                timestamp,
+                status: EntryStatus::LIVE,
+                entry: None,
+            },
+        });
+    }
+
+    /// The type of the entry at `address`, wherever it was committed
+    pub fn entry_type(&self, address: &HashString) -> Option<&EntryType> {
+        self.dht.get(address).map(|(entry_type, _)| entry_type)
+    }
+
+    /// Takes the entries and links committed since the last call
+    pub(crate) fn take_published(&mut self) -> Vec<Publication> {
+        mem::take(&mut self.published)
+    }
+
+    /// Stores an entry or link gossiped by another agent
+    pub(crate) fn store_published(&mut self, publication: Publication) {
+        match publication {
+            Publication::Entry {
+                address,
+                entry_type,
+                content,
+            } => {
+                self.dht.insert(address, (entry_type, content));
+            }
+            Publication::Link(mock_link) => {
+                if !self.links.contains(&mock_link) {
+                    self.links.push(mock_link);
+                }
+            }
+        }
+    }
+
+    /// Adds a bridge returned by `get_bridges`
+    pub fn add_bridge(&mut self, bridge: Bridge) {
+        self.bridges.push(bridge);
+    }
+
+    /// The calls the zome made to host functions, oldest first
+    pub fn calls(&self) -> &[MockCall] {
+        &self.calls
+    }
+
+    /// The calls the zome made to the host function `function`, e.g. `hc_commit_entry`
+    pub fn calls_to(&self, function: &str) -> Vec<&MockCall> {
+        self.calls.iter().filter(|call| call.function == function).collect()
+    }
+
+    /// Names of the host functions with expectations still waiting for their calls
+    pub fn pending_expectations(&self) -> Vec<&'static str> {
+        self.expectations
+            .iter()
+            .filter(|expectation| expectation.is_pending())
+            .map(|expectation| expectation.function())
+            .collect()
+    }
+
+    pub(crate) fn expect(&mut self, expectation: Expectation) {
+        self.expectations.push(expectation);
+    }
+
+    /// Answers a call to a host function with the first expectation matching it, if any
+    fn scripted(&mut self, function: &'static str, input: &Value) -> Option<Result<Value, String>> {
+        let index = self
+            .expectations
+            .iter()
+            .position(|expectation| expectation.matches(function, input))?;
+        let (output, keep) = self.expectations[index].answer();
+        if !keep {
+            self.expectations.remove(index);
+        }
+        Some(output)
+    }
+
+    /// Answers the host function calls in order with recorded results,
+    /// instead of the expectations and mock implementations
+    pub(crate) fn start_replay(&mut self, results: Vec<RecordedHostCall>) {
+        self.replay = Some(Replay {
+            results: results.into_iter().collect(),
+            ..Default::default()
+        });
+    }
+
+    /// Stops replaying, and returns how the calls diverged from the recording, if they did
+    pub(crate) fn finish_replay(&mut self) -> Option<String> {
+        let Replay {
+            results,
+            divergence,
+            ..
+        } = self.replay.take()?;
+        divergence.or_else(|| {
+            if results.is_empty() {
+                None
+            } else {
+                Some(format!("{} recorded host calls were not made", results.len()))
+            }
+        })
+    }
+
+    fn replayed(&mut self, function: &'static str, input: &Value) -> Option<Result<Value, String>> {
+        // Calls to debug are not recorded
+        if function == "hc_debug" {
+            return None;
+        }
+        let replay = self.replay.as_mut()?;
+        replay.position += 1;
+        let recorded = match replay.results.pop_front() {
+            Some(recorded) => recorded,
+            None => {
+                let divergence =
+                    format!("host call {} to {} was not recorded", replay.position, function);
+                replay.divergence.get_or_insert(divergence.clone());
+                return Some(Err(divergence));
+            }
+        };
+        // The result of another host function could not be read as this one's
+        if recorded.function != function {
+            let divergence = format!(
+                "host call {} to {} was recorded to {}",
+                replay.position, function, recorded.function
+            );
+            replay.divergence.get_or_insert(divergence.clone());
+            return Some(Err(divergence));
+        }
+        if recorded.input != *input && replay.divergence.is_none() {
+            replay.divergence = Some(format!(
+                "host call {} to {} was made with {}, recorded with {}",
+                replay.position, function, input, recorded.input
+            ));
+        }
+        Some(recorded.output)
+    }
+
+    fn record(&mut self, function: &'static str, input: Value, output: Result<Value, String>) {
+        self.calls.push(MockCall {
+            function,
+            input,
+            output,
+        });
+    }
+
+    fn agent_key_hash(&self) -> HashString {
+        self.globals
+            .get("app_agent_key_hash")
+            .and_then(|key_hash| key_hash.as_str())
+            .map(HashString::from)
+            .unwrap_or_default()
+    }
+
+    // Advances the mock clock and returns its time as an ISO 8601 string
+    fn tick(&mut self) -> String {
+        self.clock += 1;
+        format!("1970-01-01T00:00:00.{:09}Z", self.clock)
+    }
+
+    fn init_globals(&mut self, _input: Value) -> Result<Value, String> {
+        let mut globals = self.globals.clone();
+        if !globals.contains_key("timestamp") {
+            globals.insert("timestamp".to_string(), json!(self.clock));
+        }
+        Ok(Value::Object(globals))
+    }
+
+    fn debug(&mut self, msg: String) -> Result<(), String> {
+        self.debug_log.push(msg);
+        Ok(())
+    }
+
+    fn commit_entry(&mut self, input: CommitEntryArgs) -> Result<CommitEntryResult, String> {
+        let entry_type = EntryType::from_name(&input.entry_type_name)?;
+        let address = self.put_entry(entry_type.clone(), &input.entry_value);
+        let timestamp = self.tick();
+        let link = self.chain.last().map(|previous| header_address(&previous.header));
+        let link_same_type = self
+            .chain
+            .iter()
+            .rev()
+            .find(|previous| previous.header.entry_type == entry_type)
+            .map(|previous| header_address(&previous.header));
+        let header = ChainHeader {
+            entry_type,
+            timestamp,
+            link,
+            entry_address: address.clone(),
+            entry_signature: format!("mock signature of {} by {}", address, self.agent_key_hash()),
+            link_same_type,
+        };
+        self.published.push(Publication::Entry {
+            address: address.clone(),
+            entry_type: header.entry_type.clone(),
+            content: input.entry_value.clone(),
+        });
+        self.chain.push(MockChainEntry {
+            header,
+            entry: input.entry_value,
+        });
+        Ok(CommitEntryResult {
+            address: address.to_string(),
+            validation_failure: String::new(),
+        })
+    }
+
+    fn commit_entries(&mut self, input: CommitEntriesArgs) -> Result<CommitEntriesResult, String> {
+        let mut results = Vec::new();
+        for entry in input.entries {
+            results.push(self.commit_entry(entry)?);
+        }
+        Ok(CommitEntriesResult { results })
+    }
+
+    fn get_entry(&mut self, input: GetEntryArgs) -> Result<GetEntryResult, String> {
+        Ok(match self.entry(&input.address) {
+            Some(entry) => GetEntryResult {
+                status: GetResultStatus::Found,
+                entry: entry.to_string(),
+            },
+            None => GetEntryResult {
+                status: GetResultStatus::NotFound,
+                entry: String::new(),
+            },
+        })
+    }
+
+    fn get_entries(&mut self, input: GetEntriesArgs) -> Result<GetEntriesResult, String> {
+        let mut results = Vec::new();
+        for address in input.addresses {
+            results.push(self.get_entry(GetEntryArgs { address })?);
+        }
+        Ok(GetEntriesResult { results })
+    }
+
+    fn link_entries(&mut self, input: LinkEntriesArgs) -> Result<Value, String> {
+        for address in &[&input.base, &input.target] {
+            if !self.dht.contains_key(*address) {
+                return Err(format!("entry {} not found", address));
+            }
+        }
+        let author = self.agent_key_hash();
+        self.add_link(input.base, input.target, &input.tag, author);
+        if let Some(mock_link) = self.links.last() {
+            self.published.push(Publication::Link(mock_link.clone()));
+        }
+        Ok(Value::Null)
+    }
+
+    fn get_links(&mut self, input: GetLinksArgs) -> Result<GetLinksResult, String> {
+        let GetLinksArgs { base, tag, options } = input;
+        let mut links: Vec<Link> = self
+            .links
+            .iter()
+            .filter(|mock_link| mock_link.base == base && mock_link.tag == tag)
+            .filter(|mock_link| {
+                options.include_deleted || mock_link.link.status.contains(EntryStatus::LIVE)
+            })
+            .map(|mock_link| mock_link.link.clone())
+            .collect();
+        match options.sort {
+            LinkSortOrder::Unsorted => (),
+            LinkSortOrder::TimestampAscending => links.sort_by(|a, b| a.timestamp.cmp(&b.timestamp)),
+            LinkSortOrder::TimestampDescending => links.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)),
+        }
+
+        // Page tokens are the index of the first link of the page
+        let start = match options.page_token {
+            Some(ref token) => token
+                .parse::<usize>()
+                .map_err(|_| format!("invalid page token: {}", token))?,
+            None => 0,
+        }.min(links.len());
+        let end = if options.limit == 0 {
+            links.len()
+        } else {
+            (start + options.limit as usize).min(links.len())
+        };
+        let next_page_token = if end < links.len() {
+            Some(end.to_string())
+        } else {
+            None
+        };
+
+        let mut page = links[start..end].to_vec();
+        if options.load_entries {
+            for link in page.iter_mut() {
+                link.entry = self.entry(&link.target).map(|entry| entry.to_string());
+            }
+        }
+        Ok(GetLinksResult {
+            links: page,
+            next_page_token,
+        })
+    }
+
+    fn query(&mut self, input: QueryArgs) -> Result<QueryResult, String> {
+        let limit = if input.limit == 0 {
+            self.chain.len()
+        } else {
+            input.limit as usize
+        };
+        let found: Vec<&MockChainEntry> = self
+            .chain
+            .iter()
+            .rev()
+            .filter(|chain_entry| chain_entry.header.entry_type == input.entry_type)
+            .take(limit)
+            .collect();
+        Ok(QueryResult {
+            addresses: found
+                .iter()
+                .map(|chain_entry| chain_entry.header.entry_address.clone())
+                .collect(),
+            headers: if input.headers {
+                found.iter().map(|chain_entry| chain_entry.header.clone()).collect()
+            } else {
+                Vec::new()
+            },
+        })
+    }
+
+    fn get_header(&mut self, input: GetHeaderArgs) -> Result<GetHeaderResult, String> {
+        Ok(GetHeaderResult {
+            header: self
+                .chain
+                .iter()
+                .rev()
+                .find(|chain_entry| chain_entry.header.entry_address == input.entry_address)
+                .map(|chain_entry| chain_entry.header.clone()),
+        })
+    }
+
+    fn get_bridges(&mut self, _input: Value) -> Result<GetBridgesResult, String> {
+        Ok(GetBridgesResult {
+            bridges: self.bridges.clone(),
+        })
+    }
+
+    fn bridge_call(&mut self, input: BridgeCallArgs) -> Result<BridgeCallResult, String> {
+        Err(format!(
+            "bridge {} can not be called through the mock host",
+            input.bridge_name
+        ))
+    }
+}
+
+/// Address the mock host gives to content of the given type
+fn mock_address(entry_type_name: &str, content: &str) -> HashString {
+    let mut hasher = DefaultHasher::new();
+    entry_type_name.hash(&mut hasher);
+    content.hash(&mut hasher);
+    HashString::from(format!("QmMock{:016x}", hasher.finish()))
+}
+
+fn header_address(header: &ChainHeader) -> HashString {
+    let header = ::serde_json::to_string(header).expect("header should serialize to JSON");
+    mock_address("%chain_header", &header)
+}
+
+/// Takes the input of a host function from the mock memory, and stores its result there
+/// for the zome to read: the first expectation matching the call, or else the result of `f`.
+/// `f` runs without borrowing the mock host, so it can switch to another mock agent.
+fn handle_unborrowed<I, O, F>(function: &'static str, encoded_allocation_of_input: u32, f: F) -> u32
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    F: FnOnce(I) -> Result<O, String>,
+{
+    memory::store(run(function, encoded_allocation_of_input, f))
+}
+
+/// Takes the input of a host function from the mock memory, and returns its result as JSON
+fn run<I, O, F>(function: &'static str, encoded_allocation_of_input: u32, f: F) -> Result<String, String>
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    F: FnOnce(I) -> Result<O, String>,
+{
+    memory::take(encoded_allocation_of_input)
+        .and_then(|json| {
+            if json.is_empty() {
+                Ok(Value::Null)
+            } else {
+                ::serde_json::from_str(&json).map_err(|_| json)
+            }
+        })
+        .and_then(|input: Value| {
+            let replayed = with_host(|host| {
+                host.replayed(function, &input)
+                    .or_else(|| host.scripted(function, &input))
+            });
+            let output = match replayed {
+                Some(output) => output,
+                None => ::serde_json::from_value(input.clone())
+                    .map_err(|err| err.to_string())
+                    .and_then(f)
+                    .and_then(|output| {
+                        ::serde_json::to_value(output).map_err(|err| err.to_string())
+                    }),
+            };
+            with_host(|host| host.record(function, input, output.clone()));
+            output
+        })
+        .map(|output| output.to_string())
+}
+
+/// Same as `handle_unborrowed`, for host functions implemented by the mock host itself
+fn handle<I, O, F>(function: &'static str, encoded_allocation_of_input: u32, f: F) -> u32
+where
+    I: DeserializeOwned,
+    O: Serialize,
+    F: FnOnce(&mut MockHost, I) -> Result<O, String>,
+{
+    handle_unborrowed(function, encoded_allocation_of_input, |input| {
+        with_host(|host| f(host, input))
+    })
+}
+
+// The zome API functions implemented by the mock host
+// WARNING Names must be in sync with the externs in globals.rs
+
+pub(crate) unsafe extern "C" fn hc_init_globals(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_init_globals", encoded_allocation_of_input, MockHost::init_globals)
+}
+
+pub(crate) unsafe extern "C" fn hc_debug(encoded_allocation_of_input: u32) -> u32 {
+    // Panics inside `with_host` are reported while the mock host is borrowed:
+    // their report is dropped rather than panicking again inside the panic hook
+    if HOST.with(|host| host.try_borrow_mut().is_err()) {
+        let _ = memory::take(encoded_allocation_of_input);
+        return 0;
+    }
+    // Like the ribosome, returns nothing: a stored result would never be taken
+    let _ = run("hc_debug", encoded_allocation_of_input, |msg| {
+        with_host(|host| host.debug(msg))
+    });
+    0
+}
+
+pub(crate) unsafe extern "C" fn hc_commit_entry(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_commit_entry", encoded_allocation_of_input, MockHost::commit_entry)
+}
+
+pub(crate) unsafe extern "C" fn hc_commit_entries(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_commit_entries", encoded_allocation_of_input, MockHost::commit_entries)
+}
+
+pub(crate) unsafe extern "C" fn hc_get_entry(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_get_entry", encoded_allocation_of_input, MockHost::get_entry)
+}
+
+pub(crate) unsafe extern "C" fn hc_get_entries(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_get_entries", encoded_allocation_of_input, MockHost::get_entries)
+}
+
+pub(crate) unsafe extern "C" fn hc_link_entries(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_link_entries", encoded_allocation_of_input, MockHost::link_entries)
+}
+
+pub(crate) unsafe extern "C" fn hc_get_links(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_get_links", encoded_allocation_of_input, MockHost::get_links)
+}
+
+pub(crate) unsafe extern "C" fn hc_query(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_query", encoded_allocation_of_input, MockHost::query)
+}
+
+pub(crate) unsafe extern "C" fn hc_get_header(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_get_header", encoded_allocation_of_input, MockHost::get_header)
+}
+
+pub(crate) unsafe extern "C" fn hc_get_bridges(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_get_bridges", encoded_allocation_of_input, MockHost::get_bridges)
+}
+
+pub(crate) unsafe extern "C" fn hc_bridge_call(encoded_allocation_of_input: u32) -> u32 {
+    handle("hc_bridge_call", encoded_allocation_of_input, MockHost::bridge_call)
+}
+
+pub(crate) unsafe extern "C" fn hc_send(encoded_allocation_of_input: u32) -> u32 {
+    handle_unborrowed("hc_send", encoded_allocation_of_input, network::send)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn debug_stores_no_result() {
+        ::mock::reset();
+        let input = memory::store_value(&"hello").unwrap();
+        assert_eq!(unsafe { hc_debug(input) }, 0);
+        assert!(memory::is_empty());
+        with_host(|host| assert_eq!(host.debug_log().to_vec(), vec!["hello".to_string()]));
+    }
+
+    #[test]
+    fn debug_drops_messages_while_the_host_is_borrowed() {
+        ::mock::reset();
+        let input = memory::store_value(&"hello").unwrap();
+        with_host(|_| assert_eq!(unsafe { hc_debug(input) }, 0));
+        assert!(memory::is_empty());
+        with_host(|host| assert!(host.debug_log().is_empty()));
+    }
+}
@@ -0,0 +1,80 @@
+//! File holding the mock memory, which stands in for the WASM memory stack natively.
+//! Values are kept as JSON strings in a table, and their "encoded allocation" is their key.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::{cell::RefCell, collections::HashMap};
+
+// Keys start above the error codes a zome function can return instead of an allocation
+const FIRST_ALLOCATION: u32 = 1 << 16;
+
+thread_local! {
+    static MEMORY: RefCell<Memory> = RefCell::new(Memory {
+        values: HashMap::new(),
+        next_allocation: FIRST_ALLOCATION,
+    });
+}
+
+struct Memory {
+    values: HashMap<u32, Result<String, String>>,
+    next_allocation: u32,
+}
+
+/// Stores a JSON string, or the error a host function failed with,
+/// and returns the encoded allocation to pass to a zome or host function.
+pub(crate) fn store(value: Result<String, String>) -> u32 {
+    MEMORY.with(|memory| {
+        let mut memory = memory.borrow_mut();
+        let allocation = memory.next_allocation;
+        memory.next_allocation += 1;
+        memory.values.insert(allocation, value);
+        allocation
+    })
+}
+
+pub(crate) fn store_value<T: Serialize>(value: &T) -> Result<u32, String> {
+    let json = ::serde_json::to_string(value).map_err(|err| err.to_string())?;
+    Ok(store(Ok(json)))
+}
+
+/// Removes the JSON string stored at this encoded allocation.
+/// An encoded allocation of 0 means there was nothing to return.
+pub(crate) fn take(encoded_allocation: u32) -> Result<String, String> {
+    if encoded_allocation == 0 {
+        return Ok(String::new());
+    }
+    MEMORY
+        .with(|memory| memory.borrow_mut().values.remove(&encoded_allocation))
+        .unwrap_or_else(|| Err(format!("error code {}", encoded_allocation)))
+}
+
+pub(crate) fn take_value<T: DeserializeOwned>(encoded_allocation: u32) -> Result<T, String> {
+    let json = take(encoded_allocation)?;
+    ::serde_json::from_str(&json).map_err(|_| json)
+}
+
+/// Reads the JSON value stored at this encoded allocation, leaving it in place
+pub(crate) fn peek_value<T: DeserializeOwned>(encoded_allocation: u32) -> Result<T, String> {
+    let json = MEMORY
+        .with(|memory| memory.borrow().values.get(&encoded_allocation).cloned())
+        .unwrap_or_else(|| Err(format!("error code {}", encoded_allocation)))?;
+    ::serde_json::from_str(&json).map_err(|_| json)
+}
+
+/// Length of the JSON string stored at this encoded allocation, 0 if there is none
+pub(crate) fn stored_len(encoded_allocation: u32) -> usize {
+    MEMORY.with(|memory| match memory.borrow().values.get(&encoded_allocation) {
+        Some(Ok(json)) => json.len(),
+        Some(Err(err)) => err.len(),
+        None => 0,
+    })
+}
+
+/// Forgets every value still stored
+pub(crate) fn clear() {
+    MEMORY.with(|memory| memory.borrow_mut().values.clear());
+}
+
+#[cfg(test)]
+pub(crate) fn is_empty() -> bool {
+    MEMORY.with(|memory| memory.borrow().values.is_empty())
+}
@@ -0,0 +1,163 @@
+//! Native stand-in for the ribosome, enabled with the `mock` feature.
+//!
+//! With the feature on, the zome API functions and the functions generated by the zome
+//! macros talk to an in-memory mock host instead of the `hc_*` functions of the ribosome,
+//! so zome logic can be unit tested with a plain `cargo test`.
+//! Each test thread has its own mock host, and its own copy of the app globals;
+//! the `APP_*` variables are read once per process, use `try_globals()` in tests instead.
+//!
+//! # Examples
+//! ```ignore
+//! zome_functions! {
+//!     create_post: |content: String| {
+//!         hdk::commit_entry("post", json!({ "content": content })).unwrap()
+//!     }
+//! }
+//!
+//! #[test]
+//! fn can_create_post() {
+//!     hdk::mock::reset();
+//!     let output = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+//!     hdk::mock::with_host(|host| assert_eq!(host.chain().len(), 1));
+//! }
+//! ```
+//!
+//! Tests can script what the host functions return, and check which ones were called:
+//! ```ignore
+//! #[test]
+//! fn reports_failed_commit() {
+//!     hdk::mock::reset();
+//!     hdk::mock::expect_commit().returns_err("chain is full");
+//!     let output = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+//!     assert_eq!(hdk::mock::calls_to("hc_commit_entry").len(), 1);
+//!     hdk::mock::verify();
+//! }
+//! ```
+
+pub(crate) mod expectation;
+pub(crate) mod host;
+pub(crate) mod memory;
+pub(crate) mod network;
+pub(crate) mod replay;
+
+pub use self::expectation::{ExpectationBuilder, TIMEOUT_ERROR};
+pub use self::host::{MockCall, MockChainEntry, MockHost};
+pub use self::network::{Harness, MockRejection, ZomeFunction};
+pub use self::replay::{replay, RecordedCall, RecordedHostCall, Recording};
+use globals::{cache_app_globals, set_api_version_checked};
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+use serde_json::Value;
+use validation::parse_validation_failure;
+use {IntoValidationErrors, ValidationError};
+
+/// Replaces the mock host of the current thread with an empty one,
+/// and forgets the app globals read from the previous one.
+pub fn reset() {
+    host::with_host(|host| *host = MockHost::default());
+    memory::clear();
+    network::clear();
+    cache_app_globals(None);
+    set_api_version_checked(false);
+}
+
+/// Runs `f` with the mock host of the current thread,
+/// e.g. to set its globals or inspect what the zome committed.
+/// Call `hdk::refresh_globals()` after changing the globals.
+pub fn with_host<R, F: FnOnce(&mut MockHost) -> R>(f: F) -> R {
+    host::with_host(f)
+}
+
+/// Calls a function generated by `zome_functions!`, `validations!` or `genesis!`
+/// with `input` as its JSON arguments, and returns its JSON output.
+/// The output is empty for callbacks that succeeded without a message.
+pub fn call(
+    zome_function: ZomeFunction,
+    input: Value,
+) -> Result<String, String> {
+    let encoded_allocation_of_input = memory::store_value(&input)?;
+    let encoded_allocation_of_output = zome_function(encoded_allocation_of_input);
+    memory::take(encoded_allocation_of_output)
+}
+
+/// Calls a validation callback generated by `validations!` with `input`,
+/// e.g. `{ "entry": .., "ctx": .. }` for an `[ENTRY]` callback,
+/// and returns the validation errors it reported.
+pub fn validate(
+    validation_callback: ZomeFunction,
+    input: Value,
+) -> Result<(), Vec<ValidationError>> {
+    match call(validation_callback, input) {
+        Ok(ref output) if output.is_empty() => Ok(()),
+        Ok(output) => Err(parse_validation_failure(&output)),
+        Err(error) => Err(error.into_validation_errors()),
+    }
+}
+
+/// JSON of the `ValidationData` given to validation callbacks
+// WARNING must be in sync with ValidationData in holochain_wasm_utils
+pub(crate) fn validation_data_json(
+    sources: &[HashString],
+    lifecycle: &str,
+    action: &str,
+) -> Value {
+    json!({
+        "sources": sources,
+        "source_chain_entries": null,
+        "source_chain_headers": null,
+        "custom": null,
+        "lifecycle": lifecycle,
+        "action": action,
+    })
+}
+
+/// Scripts the result of the calls to the host function `function`, e.g. `hc_get_links`.
+/// Expectations are checked in the order they were scripted, and answer
+/// the calls matching them instead of the mock implementation.
+pub fn expect(function: &'static str) -> ExpectationBuilder {
+    ExpectationBuilder::new(function)
+}
+
+/// Scripts the result of `commit_entry`,
+/// e.g. `expect_commit().fails_validation(errors)`
+pub fn expect_commit() -> ExpectationBuilder {
+    expect("hc_commit_entry")
+}
+
+/// Scripts the result of `get_entry`, e.g. `expect_get_entry().not_found()`
+pub fn expect_get_entry() -> ExpectationBuilder {
+    expect("hc_get_entry")
+}
+
+/// Scripts the result of `get_links` and `get_links_with_options`
+pub fn expect_get_links() -> ExpectationBuilder {
+    expect("hc_get_links")
+}
+
+/// Scripts the result of `send`, e.g. `expect_send().times_out()`
+pub fn expect_send() -> ExpectationBuilder {
+    expect("hc_send")
+}
+
+/// Scripts the result of `bridge_call`
+pub fn expect_bridge_call() -> ExpectationBuilder {
+    expect("hc_bridge_call")
+}
+
+/// The calls the zome made to host functions since the last reset, oldest first
+pub fn calls() -> Vec<MockCall> {
+    host::with_host(|host| host.calls().to_vec())
+}
+
+/// The calls the zome made to the host function `function` since the last reset
+pub fn calls_to(function: &str) -> Vec<MockCall> {
+    host::with_host(|host| host.calls_to(function).into_iter().cloned().collect())
+}
+
+/// Panics if some expectations were not met by as many calls as they were scripted for.
+/// Expectations scripted with `always()` are never pending.
+pub fn verify() {
+    let pending = host::with_host(|host| host.pending_expectations());
+    if !pending.is_empty() {
+        panic!("expected calls to {} were not made", pending.join(", "));
+    }
+}
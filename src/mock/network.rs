@@ -0,0 +1,324 @@
+//! File holding the simulated network of the mock host: several agents running the same
+//! zome natively, each with its own mock host, sharing a DHT that only propagates
+//! entries and links when the test says so.
+
+use super::{
+    call,
+    host::{self, MockHost, Publication},
+    validate, validation_data_json,
+};
+use api_serialization::{SendArgs, SendResult};
+use globals::cache_app_globals;
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+use serde_json::Value;
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData, rc::Rc};
+use {EntryType, LinkAction, SystemEntryType, ValidationError};
+
+/// A callback generated by `zome_functions!`, `validations!` or `receive!`
+pub type ZomeFunction = extern "C" fn(u32) -> u32;
+
+thread_local! {
+    static NETWORK: RefCell<Option<Network>> = RefCell::new(None);
+}
+
+struct MockAgent {
+    name: String,
+    key_hash: HashString,
+    /// Mock host of the agent, `None` while it is the mock host of the thread
+    host: Option<MockHost>,
+}
+
+#[derive(Default)]
+struct Network {
+    agents: Vec<MockAgent>,
+    current: usize,
+    validations: HashMap<String, ZomeFunction>,
+    link_validations: HashMap<String, ZomeFunction>,
+    receive: Option<ZomeFunction>,
+}
+
+fn with_network<R, F: FnOnce(&mut Network) -> R>(f: F) -> Option<R> {
+    NETWORK.with(|network| network.borrow_mut().as_mut().map(f))
+}
+
+/// Forgets the agents of the current thread
+pub(crate) fn clear() {
+    NETWORK.with(|network| *network.borrow_mut() = None);
+}
+
+/// Makes the agent at `index` the current one, and returns the index of the previous one
+fn switch_to(index: usize) -> usize {
+    let previous = with_network(|network| {
+        let previous = network.current;
+        if index != previous {
+            let host = network.agents[index]
+                .host
+                .take()
+                .expect("agent should have a parked mock host");
+            network.agents[previous].host = Some(host::replace_host(host));
+            network.current = index;
+        }
+        previous
+    }).expect("mock network should be set up");
+    // The app globals read by the zome are those of the new agent
+    if index != previous {
+        cache_app_globals(None);
+    }
+    previous
+}
+
+/// Runs `f` as the agent at `index`, then switches back to the current agent
+fn as_agent_at<R, F: FnOnce() -> R>(index: usize, f: F) -> R {
+    let _restore = RestoreAgent(switch_to(index));
+    f()
+}
+
+/// Switches back to the agent at its index when dropped, even if `f` panicked
+struct RestoreAgent(usize);
+
+impl Drop for RestoreAgent {
+    fn drop(&mut self) {
+        switch_to(self.0);
+    }
+}
+
+/// Delivers a message sent with `hdk::send` to the `receive` callback of its recipient
+pub(crate) fn send(input: SendArgs) -> Result<SendResult, String> {
+    let unreachable = || format!("agent {} can not be reached through the mock host", input.to);
+    let (recipient, from, receive) = with_network(|network| {
+        let recipient = network
+            .agents
+            .iter()
+            .position(|agent| agent.key_hash == input.to)?;
+        let from = network.agents[network.current].key_hash.clone();
+        Some((recipient, from, network.receive?))
+    }).and_then(|found| found)
+        .ok_or_else(unreachable)?;
+    let message: Value = ::serde_json::from_str(&input.message).map_err(|err| err.to_string())?;
+    let response = as_agent_at(recipient, || {
+        call(receive, json!({ "from": from, "message": message }))
+    })?;
+    Ok(SendResult { response })
+}
+
+/// Validation data the mock network gives to validation callbacks
+fn validation_data(author: &HashString) -> Value {
+    validation_data_json(&[author.clone()], "Dht", "Commit")
+}
+
+/// An entry or link an agent refused to store when it was gossiped to them
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockRejection {
+    /// Name of the agent that validated the entry or link
+    pub agent: String,
+    /// Address of the entry, or of the target of the link
+    pub address: HashString,
+    pub errors: Vec<ValidationError>,
+}
+
+/// Simulates several agents running the zome under test, sharing one DHT.
+///
+/// Entries and links committed by an agent are only seen by the others once `sync()`
+/// gossips them, after running the validation callbacks of the receiving agents.
+/// Messages sent with `hdk::send` are delivered right away to the `receive` callback.
+/// The harness uses the mock host of the current thread, which it resets when created
+/// and dropped.
+///
+/// # Examples
+/// ```ignore
+/// let harness = hdk::mock::Harness::new(&["alice", "bob"]);
+/// harness.validation("post", validate_post).receive(receive);
+/// harness.call("alice", create_post, json!({ "content": "hello" })).unwrap();
+/// assert!(harness.call("bob", list_posts, json!({})).unwrap() == "[]");
+/// harness.sync();
+/// assert!(harness.call("bob", list_posts, json!({})).unwrap() == r#"["hello"]"#);
+/// ```
+pub struct Harness {
+    // The harness drives thread local state
+    _not_send: PhantomData<Rc<()>>,
+}
+
+impl Harness {
+    /// Creates the agents named `names`, with the first one as the current agent
+    pub fn new(names: &[&str]) -> Harness {
+        assert!(!names.is_empty(), "the mock network needs at least one agent");
+        super::reset();
+        let mut agents: Vec<MockAgent> = names
+            .iter()
+            .map(|name| {
+                let mut host = MockHost::default();
+                // Every agent knows the agent and key entries of the others
+                for other in names {
+                    host.put_entry(EntryType::System(SystemEntryType::Agent), other);
+                    host.put_entry(EntryType::System(SystemEntryType::Key), other);
+                }
+                let agent_address = host.put_entry(EntryType::System(SystemEntryType::Agent), name);
+                let key_hash = host.put_entry(EntryType::System(SystemEntryType::Key), name);
+                host.set_global("app_agent_id_str", name);
+                host.set_global("app_agent_key_hash", &key_hash);
+                host.set_global("app_agent_initial_hash", &agent_address);
+                host.set_global("app_agent_latest_hash", &agent_address);
+                MockAgent {
+                    name: name.to_string(),
+                    key_hash,
+                    host: Some(host),
+                }
+            })
+            .collect();
+        let first_host = agents[0].host.take().expect("agent should have a mock host");
+        host::replace_host(first_host);
+        let network = Network {
+            agents,
+            ..Default::default()
+        };
+        NETWORK.with(|current| *current.borrow_mut() = Some(network));
+        Harness {
+            _not_send: PhantomData,
+        }
+    }
+
+    /// Registers the `[ENTRY]` validation callback of `entry_type`,
+    /// run by the agents receiving entries of that type in `sync()`
+    pub fn validation(&self, entry_type: &str, callback: ZomeFunction) -> &Harness {
+        with_network(|network| network.validations.insert(entry_type.to_string(), callback));
+        self
+    }
+
+    /// Registers the `[LINK]` validation callback of the links from entries of `entry_type`
+    pub fn link_validation(&self, entry_type: &str, callback: ZomeFunction) -> &Harness {
+        with_network(|network| {
+            network
+                .link_validations
+                .insert(entry_type.to_string(), callback)
+        });
+        self
+    }
+
+    /// Registers the callback generated by `receive!`
+    pub fn receive(&self, callback: ZomeFunction) -> &Harness {
+        with_network(|network| network.receive = Some(callback));
+        self
+    }
+
+    /// Key hash of the agent `name`, to `send` messages to them
+    pub fn key_hash(&self, name: &str) -> HashString {
+        let index = self.index_of(name);
+        with_network(|network| network.agents[index].key_hash.clone())
+            .expect("mock network should be set up")
+    }
+
+    /// Calls a zome function as the agent `name`, see `hdk::mock::call`
+    pub fn call(
+        &self,
+        name: &str,
+        zome_function: ZomeFunction,
+        input: Value,
+    ) -> Result<String, String> {
+        self.as_agent(name, || call(zome_function, input))
+    }
+
+    /// Runs `f` as the agent `name`, e.g. to call the zome API functions directly
+    pub fn as_agent<R, F: FnOnce() -> R>(&self, name: &str, f: F) -> R {
+        as_agent_at(self.index_of(name), f)
+    }
+
+    /// Runs `f` with the mock host of the agent `name`
+    pub fn with_host<R, F: FnOnce(&mut MockHost) -> R>(&self, name: &str, f: F) -> R {
+        self.as_agent(name, || host::with_host(f))
+    }
+
+    /// Gossips the entries and links committed since the last sync to every other agent.
+    /// Returns those the receiving agents refused to store because they failed validation.
+    pub fn sync(&self) -> Vec<MockRejection> {
+        let count = with_network(|network| network.agents.len()).unwrap_or(0);
+        let mut rejections = Vec::new();
+        for author in 0..count {
+            let published = as_agent_at(author, || host::with_host(|host| host.take_published()));
+            for publication in published {
+                for receiver in (0..count).filter(|receiver| *receiver != author) {
+                    match validate_publication(author, receiver, &publication) {
+                        Ok(()) => as_agent_at(receiver, || {
+                            host::with_host(|host| host.store_published(publication.clone()))
+                        }),
+                        Err(errors) => rejections.push(MockRejection {
+                            agent: self.name_of(receiver),
+                            address: match publication {
+                                Publication::Entry { ref address, .. } => address.clone(),
+                                Publication::Link(ref mock_link) => mock_link.link.target.clone(),
+                            },
+                            errors,
+                        }),
+                    }
+                }
+            }
+        }
+        rejections
+    }
+
+    fn index_of(&self, name: &str) -> usize {
+        with_network(|network| network.agents.iter().position(|agent| agent.name == name))
+            .and_then(|index| index)
+            .unwrap_or_else(|| panic!("unknown mock agent: {}", name))
+    }
+
+    fn name_of(&self, index: usize) -> String {
+        with_network(|network| network.agents[index].name.clone())
+            .expect("mock network should be set up")
+    }
+}
+
+impl Drop for Harness {
+    fn drop(&mut self) {
+        super::reset();
+    }
+}
+
+/// Runs the validation callback of a gossiped entry or link as the agent `receiver`
+fn validate_publication(
+    author: usize,
+    receiver: usize,
+    publication: &Publication,
+) -> Result<(), Vec<ValidationError>> {
+    let author = with_network(|network| network.agents[author].key_hash.clone())
+        .expect("mock network should be set up");
+    let (callback, input) = match *publication {
+        Publication::Entry {
+            ref entry_type,
+            ref content,
+            ..
+        } => {
+            let callback = with_network(|network| {
+                network.validations.get(&entry_type.to_string()).cloned()
+            });
+            let entry: Value = ::serde_json::from_str(content).unwrap_or(Value::Null);
+            (
+                callback.and_then(|callback| callback),
+                json!({ "entry": entry, "ctx": validation_data(&author) }),
+            )
+        }
+        Publication::Link(ref mock_link) => {
+            let base_type = as_agent_at(receiver, || {
+                host::with_host(|host| host.entry_type(&mock_link.base).map(|t| t.to_string()))
+            });
+            let callback = base_type.and_then(|base_type| {
+                with_network(|network| network.link_validations.get(&base_type).cloned())
+                    .and_then(|callback| callback)
+            });
+            (
+                callback,
+                json!({
+                    "base": mock_link.base,
+                    "target": mock_link.link.target,
+                    "tag": mock_link.tag,
+                    "action": LinkAction::Add,
+                    "ctx": validation_data(&author),
+                }),
+            )
+        }
+    };
+    let callback = match callback {
+        Some(callback) => callback,
+        None => return Ok(()),
+    };
+    as_agent_at(receiver, || validate(callback, input))
+}
@@ -0,0 +1,118 @@
+//! File holding the replay of zome function calls recorded with the `record` feature:
+//! the zome function runs natively against the mock host, which answers its host calls
+//! with the recorded results, to reproduce the recorded execution.
+
+use super::{call, host::with_host, reset, ZomeFunction};
+use globals::set_api_version_checked;
+use record::RecordEvent;
+use serde_json::Value;
+
+/// A zome function or callback call read from a recording
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedCall {
+    pub function: String,
+    pub input: Value,
+    /// Host function calls, in order
+    pub host_calls: Vec<RecordedHostCall>,
+    /// Output of the zome function, `None` if the recording stopped before it returned,
+    /// e.g. when it trapped
+    pub output: Option<Value>,
+}
+
+/// A host function call made by a recorded zome function call
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedHostCall {
+    /// Name of the host function, e.g. `hc_commit_entry`
+    pub function: String,
+    pub input: Value,
+    pub output: Result<Value, String>,
+}
+
+/// The zome function calls recorded in a debug log
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Recording {
+    pub calls: Vec<RecordedCall>,
+}
+
+impl Recording {
+    /// Reads the records of a debug log, e.g. the log of a conductor.
+    /// Lines without a record are skipped.
+    pub fn from_log(log: &str) -> Result<Recording, String> {
+        let mut recording = Recording::default();
+        let records = log.lines().enumerate().filter_map(|(index, line)| {
+            RecordEvent::from_log_line(line).map(|record| (index + 1, record))
+        });
+        for (line_number, record) in records {
+            match record {
+                RecordEvent::ZomeCall { function, input } => recording.calls.push(RecordedCall {
+                    function,
+                    input,
+                    host_calls: Vec::new(),
+                    output: None,
+                }),
+                RecordEvent::HostCall {
+                    function,
+                    input,
+                    output,
+                } => recording
+                    .current_call(line_number)?
+                    .host_calls
+                    .push(RecordedHostCall {
+                        function,
+                        input,
+                        output,
+                    }),
+                RecordEvent::ZomeOutput { output } => {
+                    recording.current_call(line_number)?.output = Some(output)
+                }
+            }
+        }
+        Ok(recording)
+    }
+
+    /// The recorded calls of the zome function `function`, oldest first
+    pub fn calls_of(&self, function: &str) -> Vec<&RecordedCall> {
+        self.calls.iter().filter(|call| call.function == function).collect()
+    }
+
+    fn current_call(&mut self, line_number: usize) -> Result<&mut RecordedCall, String> {
+        match self.calls.last_mut() {
+            Some(ref call) if call.output.is_some() => Err(format!(
+                "line {}: host call recorded after the output of {}",
+                line_number, call.function
+            )),
+            Some(call) => Ok(call),
+            None => Err(format!("line {}: host call recorded outside of a zome call", line_number)),
+        }
+    }
+}
+
+/// Replays a recorded call of `zome_function` against a fresh mock host, and returns
+/// the output of the zome function.
+/// Fails if the zome function made other host calls than recorded, or returned
+/// another output than recorded.
+pub fn replay(recorded_call: &RecordedCall, zome_function: ZomeFunction) -> Result<String, String> {
+    reset();
+    // Calls not starting by reading the globals were recorded after the API version check
+    let api_version_checked = recorded_call
+        .host_calls
+        .first()
+        .map_or(true, |host_call| host_call.function != "hc_init_globals");
+    set_api_version_checked(api_version_checked);
+    with_host(|host| host.start_replay(recorded_call.host_calls.clone()));
+    let output = call(zome_function, recorded_call.input.clone());
+    if let Some(divergence) = with_host(|host| host.finish_replay()) {
+        return Err(divergence);
+    }
+    let output = output?;
+    if let Some(ref recorded_output) = recorded_call.output {
+        let replayed_output: Value = ::serde_json::from_str(&output).unwrap_or(Value::Null);
+        if replayed_output != *recorded_output {
+            return Err(format!(
+                "replayed output {} differs from recorded output {}",
+                output, recorded_output
+            ));
+        }
+    }
+    Ok(output)
+}
@@ -0,0 +1,99 @@
+//! File holding the recording mode of the `record` feature: the zome logs every call of its
+//! zome functions and callbacks, with the input and output of each host function they call,
+//! so the execution can be replayed natively with `hdk::mock::replay`.
+//!
+//! Records are logged through `debug`, one JSON object per message, e.g.
+//! `{"hdk_record":{"host_call":{"function":"hc_get_entry","input":{..},"output":{"Ok":{..}}}}}`.
+//! Calls to `debug` are not recorded.
+
+use serde::Serialize;
+use serde_json::Value;
+
+/// Key of the JSON object holding a record in the debug log
+pub const RECORD_KEY: &str = "hdk_record";
+
+/// An event of a recorded zome function call
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RecordEvent {
+    /// A zome function or callback starts with this input
+    ZomeCall { function: String, input: Value },
+    /// The zome function called the host function `function`, which returned `output`
+    HostCall {
+        function: String,
+        input: Value,
+        output: Result<Value, String>,
+    },
+    /// The zome function returned `output`
+    ZomeOutput { output: Value },
+}
+
+#[derive(Serialize, Deserialize)]
+struct RecordLine {
+    hdk_record: RecordEvent,
+}
+
+impl RecordEvent {
+    /// Finds a record in a line of the debug log, which may hold text before it
+    pub fn from_log_line(line: &str) -> Option<RecordEvent> {
+        let start = line.find(&format!("{{\"{}\"", RECORD_KEY))?;
+        ::serde_json::from_str::<RecordLine>(line[start..].trim())
+            .ok()
+            .map(|record_line| record_line.hdk_record)
+    }
+}
+
+/// Records the start of the zome function or callback `function`, called by the zome macros
+#[cfg(feature = "record")]
+pub fn zome_call(function: &str, encoded_allocation_of_input: u32) {
+    let input = read_input(encoded_allocation_of_input).unwrap_or(Value::Null);
+    log(RecordEvent::ZomeCall {
+        function: function.to_string(),
+        input,
+    });
+}
+
+#[cfg(not(feature = "record"))]
+#[inline(always)]
+pub fn zome_call(_function: &str, _encoded_allocation_of_input: u32) {}
+
+/// Records the output of the zome function, called by the zome macros
+#[cfg(feature = "record")]
+pub fn zome_output<O: Serialize>(output: &O) {
+    let output = ::serde_json::to_value(output).unwrap_or(Value::Null);
+    log(RecordEvent::ZomeOutput { output });
+}
+
+#[cfg(not(feature = "record"))]
+#[inline(always)]
+pub fn zome_output<O: Serialize>(_output: &O) {}
+
+/// Records a call to a host function
+#[cfg(feature = "record")]
+pub(crate) fn host_call(function: &str, input: &Value, output: &Result<Value, String>) {
+    log(RecordEvent::HostCall {
+        function: function.to_string(),
+        input: input.clone(),
+        output: output.clone(),
+    });
+}
+
+#[cfg(feature = "record")]
+fn log(record: RecordEvent) {
+    let line = RecordLine { hdk_record: record };
+    if let Ok(line) = ::serde_json::to_string(&line) {
+        // A failure to log must not change the execution being recorded
+        let _ = ::debug(&line);
+    }
+}
+
+// The input of the zome function is still to be read by the zome macros
+#[cfg(all(feature = "record", not(feature = "mock")))]
+fn read_input(encoded_allocation_of_input: u32) -> Result<Value, String> {
+    ::deserialize_wasm_input(encoded_allocation_of_input)
+}
+
+#[cfg(all(feature = "record", feature = "mock"))]
+fn read_input(encoded_allocation_of_input: u32) -> Result<Value, String> {
+    ::mock::memory::peek_value(encoded_allocation_of_input)
+}
@@ -0,0 +1,278 @@
+//! File holding helpers for testing zomes: typed calls to the functions generated by
+//! `zome_functions!`, and the manifest of the functions exported by a compiled zome,
+//! to declare them in the DNA of a test instance.
+//!
+//! The DNA itself is left to the test utilities of holochain-rust, which the hdk does not
+//! depend on: pass `ZomeManifest::function_names` to the capability they build,
+//! as `tests/integration_test.rs` does.
+
+use serde::{de::DeserializeOwned, Serialize};
+use std::collections::HashMap;
+use zome_call::CALLBACK_MARKER_MAGIC;
+
+/// Serializes a typed input into the JSON arguments of a zome function
+pub fn input_json<I: Serialize>(input: &I) -> String {
+    ::serde_json::to_string(input).expect("zome function input should serialize to JSON")
+}
+
+/// Decodes the JSON output of a zome function into a typed value.
+/// The error holds the raw output, e.g. the JSON of a `RibosomeError`.
+pub fn decode_output<O: DeserializeOwned>(output: &str) -> Result<O, String> {
+    ::serde_json::from_str(output)
+        .map_err(|err| format!("could not decode zome function output {}: {}", output, err))
+}
+
+/// Calls a function generated by `zome_functions!` natively against the mock host,
+/// with a typed input, and decodes its output.
+///
+/// # Examples
+/// ```ignore
+/// #[derive(Serialize)]
+/// struct CreatePostInput {
+///     content: String,
+/// }
+///
+/// let address: HashString = hdk::testing::call(create_post, &CreatePostInput {
+///     content: "hello".to_string(),
+/// }).unwrap();
+/// ```
+#[cfg(feature = "mock")]
+pub fn call<I: Serialize, O: DeserializeOwned>(
+    zome_function: ::mock::ZomeFunction,
+    input: &I,
+) -> Result<O, String> {
+    let input = ::serde_json::to_value(input).map_err(|err| err.to_string())?;
+    let output = ::mock::call(zome_function, input)?;
+    decode_output(&output)
+}
+
+/// The functions exported by a zome compiled to WASM
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ZomeManifest {
+    /// Zome functions, in export order
+    pub functions: Vec<String>,
+    /// Callbacks called by the ribosome, generated by `validations!`, `genesis!`,
+    /// `bridge_genesis!` or `receive!`, e.g. `genesis` or `validate_post`
+    pub callbacks: Vec<String>,
+}
+
+impl ZomeManifest {
+    /// Reads the functions exported by the WASM module `wasm`.
+    /// Callbacks are told from zome functions by the marker the callback macros place
+    /// in the data of the zome, so callbacks written without those macros are listed
+    /// as zome functions.
+    /// Exports starting with `__`, like `__hdk_version`, are internal to the hdk and skipped.
+    pub fn from_wasm(wasm: &[u8]) -> Result<ZomeManifest, String> {
+        let module = WasmModule::parse(wasm)?;
+        let callbacks = module.marked_functions(&CALLBACK_MARKER_MAGIC);
+        let mut manifest = ZomeManifest::default();
+        for (name, function) in module.exports {
+            if name.starts_with("__") {
+                continue;
+            } else if callbacks.contains(&function) {
+                manifest.callbacks.push(name);
+            } else {
+                manifest.functions.push(name);
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Names of the zome functions, to declare in the capability of a test DNA
+    pub fn function_names(&self) -> Vec<&str> {
+        self.functions.iter().map(|name| name.as_str()).collect()
+    }
+}
+
+// Sections of a WASM module start with their id and size
+const WASM_MAGIC: &[u8] = b"\0asm";
+const WASM_EXPORT_SECTION: u8 = 7;
+const WASM_ELEMENT_SECTION: u8 = 9;
+const WASM_DATA_SECTION: u8 = 11;
+const WASM_FUNCTION_EXPORT: u8 = 0;
+const WASM_I32_CONST: u8 = 0x41;
+const WASM_GLOBAL_GET: u8 = 0x23;
+const WASM_END: u8 = 0x0b;
+
+/// The parts of a WASM module telling which exported function a callback marker points to
+#[derive(Default)]
+struct WasmModule<'a> {
+    /// Name and function index of the exported functions, in export order
+    exports: Vec<(String, u32)>,
+    /// Function index of the elements of the table, by table index.
+    /// Function pointers stored in memory are table indices.
+    table: HashMap<u32, u32>,
+    /// Content of the data segments
+    data: Vec<&'a [u8]>,
+}
+
+impl<'a> WasmModule<'a> {
+    fn parse(wasm: &'a [u8]) -> Result<WasmModule<'a>, String> {
+        if wasm.len() < 8 || &wasm[0..4] != WASM_MAGIC {
+            return Err("not a WASM module".to_string());
+        }
+        let mut reader = WasmReader { wasm, position: 8 };
+        let mut module = WasmModule::default();
+        while !reader.is_done() {
+            let section_id = reader.byte()?;
+            let section_size = reader.leb128()? as usize;
+            let section_end = reader.position + section_size;
+            match section_id {
+                WASM_EXPORT_SECTION => for _ in 0..reader.leb128()? {
+                    let name = reader.name()?;
+                    let kind = reader.byte()?;
+                    let index = reader.leb128()?;
+                    if kind == WASM_FUNCTION_EXPORT {
+                        module.exports.push((name, index));
+                    }
+                },
+                WASM_ELEMENT_SECTION => for _ in 0..reader.leb128()? {
+                    if reader.leb128()? != 0 {
+                        return Err("unsupported element segment in WASM module".to_string());
+                    }
+                    let offset = match reader.offset()? {
+                        Some(offset) => offset as u32,
+                        None => return Err("unsupported element offset in WASM module".to_string()),
+                    };
+                    for element in 0..reader.leb128()? {
+                        let function = reader.leb128()?;
+                        let table_index = offset
+                            .checked_add(element)
+                            .ok_or_else(|| "element segment outside of the WASM table".to_string())?;
+                        module.table.insert(table_index, function);
+                    }
+                },
+                WASM_DATA_SECTION => for _ in 0..reader.leb128()? {
+                    match reader.leb128()? {
+                        // Active segment of the memory 0, passive segment, active segment
+                        0 => {
+                            reader.offset()?;
+                        }
+                        1 => (),
+                        2 => {
+                            reader.leb128()?;
+                            reader.offset()?;
+                        }
+                        _ => return Err("unsupported data segment in WASM module".to_string()),
+                    }
+                    let length = reader.leb128()? as usize;
+                    module.data.push(reader.bytes(length)?);
+                },
+                _ => (),
+            }
+            reader.position = section_end;
+        }
+        Ok(module)
+    }
+
+    /// Function indices of the functions pointed to by the markers starting with `magic`.
+    /// Matches not followed by an index of the table are other data, and skipped.
+    fn marked_functions(&self, magic: &[u8]) -> Vec<u32> {
+        let mut functions = Vec::new();
+        for segment in &self.data {
+            let mut position = 0;
+            while let Some(found) = find(&segment[position..], magic) {
+                let pointer = position + found + magic.len();
+                let function = segment
+                    .get(pointer..pointer + 4)
+                    .map(|bytes| {
+                        bytes.iter().rev().fold(0u32, |value, &byte| value << 8 | u32::from(byte))
+                    })
+                    .and_then(|table_index| self.table.get(&table_index));
+                match function {
+                    Some(function) => {
+                        functions.push(*function);
+                        position = pointer + 4;
+                    }
+                    None => position = position + found + 1,
+                }
+            }
+        }
+        functions
+    }
+}
+
+fn find(bytes: &[u8], pattern: &[u8]) -> Option<usize> {
+    bytes.windows(pattern.len()).position(|window| window == pattern)
+}
+
+struct WasmReader<'a> {
+    wasm: &'a [u8],
+    position: usize,
+}
+
+impl<'a> WasmReader<'a> {
+    fn is_done(&self) -> bool {
+        self.position >= self.wasm.len()
+    }
+
+    fn byte(&mut self) -> Result<u8, String> {
+        let byte = *self
+            .wasm
+            .get(self.position)
+            .ok_or_else(|| "unexpected end of WASM module".to_string())?;
+        self.position += 1;
+        Ok(byte)
+    }
+
+    fn bytes(&mut self, length: usize) -> Result<&'a [u8], String> {
+        let end = self.position + length;
+        let bytes = self
+            .wasm
+            .get(self.position..end)
+            .ok_or_else(|| "unexpected end of WASM module".to_string())?;
+        self.position = end;
+        Ok(bytes)
+    }
+
+    // Unsigned LEB128, as used for sizes, counts and indices
+    fn leb128(&mut self) -> Result<u32, String> {
+        let mut value = 0u32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("invalid integer in WASM module".to_string())
+    }
+
+    // Signed LEB128, as used for constants
+    fn sleb128(&mut self) -> Result<i32, String> {
+        let mut value = 0i32;
+        for shift in (0..35).step_by(7) {
+            let byte = self.byte()?;
+            value |= i32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                if shift < 25 && byte & 0x40 != 0 {
+                    value |= -1 << (shift + 7);
+                }
+                return Ok(value);
+            }
+        }
+        Err("invalid integer in WASM module".to_string())
+    }
+
+    // Offset of a segment: a constant, or `None` when read from a global
+    fn offset(&mut self) -> Result<Option<i32>, String> {
+        let offset = match self.byte()? {
+            WASM_I32_CONST => Some(self.sleb128()?),
+            WASM_GLOBAL_GET => {
+                self.leb128()?;
+                None
+            }
+            _ => return Err("unsupported offset in WASM module".to_string()),
+        };
+        if self.byte()? != WASM_END {
+            return Err("unsupported offset in WASM module".to_string());
+        }
+        Ok(offset)
+    }
+
+    fn name(&mut self) -> Result<String, String> {
+        let length = self.leb128()? as usize;
+        let bytes = self.bytes(length)?;
+        String::from_utf8(bytes.to_vec()).map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,294 @@
+//! File holding the types used to report validation failures
+//! and the field rules run by the `validations!` macro
+
+use get_entry;
+use holochain_wasm_utils::{
+    api_serialization::validation::ValidationData, holochain_core_types::hash::HashString,
+};
+#[cfg(feature = "regex")]
+use regex::Regex;
+#[cfg(feature = "regex")]
+use std::{cell::RefCell, collections::HashMap};
+use std::fmt::Display;
+use RibosomeError;
+
+/// Error code given to failures reported as a plain message
+pub const CUSTOM_VALIDATION_ERROR_CODE: &str = "custom";
+
+/// A single reason for rejecting an entry or link.
+/// A validation callback can report several of them at once.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    /// Machine readable identifier of the failed rule, e.g. `"length"`
+    pub code: String,
+    /// Path of the offending field inside the entry, e.g. `"author.name"`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    /// Human readable description of the failure
+    pub message: String,
+}
+
+impl ValidationError {
+    pub fn new<S: Into<String>, M: Into<String>>(code: S, message: M) -> ValidationError {
+        ValidationError {
+            code: code.into(),
+            field: None,
+            message: message.into(),
+        }
+    }
+
+    /// Attach the path of the offending field
+    pub fn with_field<S: Into<String>>(mut self, field: S) -> ValidationError {
+        self.field = Some(field.into());
+        self
+    }
+}
+
+/// Anything a validation callback may return as its error.
+/// Plain strings become a single `ValidationError` with the `custom` code.
+pub trait IntoValidationErrors {
+    fn into_validation_errors(self) -> Vec<ValidationError>;
+}
+
+impl IntoValidationErrors for String {
+    fn into_validation_errors(self) -> Vec<ValidationError> {
+        vec![ValidationError::new(CUSTOM_VALIDATION_ERROR_CODE, self)]
+    }
+}
+
+impl<'a> IntoValidationErrors for &'a str {
+    fn into_validation_errors(self) -> Vec<ValidationError> {
+        self.to_string().into_validation_errors()
+    }
+}
+
+impl IntoValidationErrors for ValidationError {
+    fn into_validation_errors(self) -> Vec<ValidationError> {
+        vec![self]
+    }
+}
+
+impl IntoValidationErrors for Vec<ValidationError> {
+    fn into_validation_errors(self) -> Vec<ValidationError> {
+        self
+    }
+}
+
+/// Parse the validation failure reported by the ribosome.
+/// Failures not produced by the `validations!` macro are kept as a single `custom` error.
+pub(crate) fn parse_validation_failure(failure: &str) -> Vec<ValidationError> {
+    match ::serde_json::from_str::<Vec<ValidationError>>(failure) {
+        Ok(errors) => errors,
+        Err(_) => failure.into_validation_errors(),
+    }
+}
+
+/// Field level rules of an entry type, usually implemented with `#[derive(Validate)]`
+/// from the `hdk_derive` crate.
+/// The `validations!` macro runs them before the custom validation body.
+pub trait Validate {
+    fn validate(&self) -> Result<(), Vec<ValidationError>>;
+}
+
+/// Values the `length` and `non_empty` rules can be applied to
+pub trait HasLength {
+    fn length(&self) -> usize;
+}
+
+impl HasLength for String {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl<'a> HasLength for &'a str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl<T> HasLength for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+pub fn validate_non_empty<T: HasLength>(value: &T, field: &str) -> Result<(), ValidationError> {
+    if value.length() == 0 {
+        Err(ValidationError::new("non_empty", format!("{} must not be empty", field)).with_field(field))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn validate_length<T: HasLength>(
+    value: &T,
+    min: Option<usize>,
+    max: Option<usize>,
+    field: &str,
+) -> Result<(), ValidationError> {
+    let length = value.length();
+    if min.map_or(false, |min| length < min) || max.map_or(false, |max| length > max) {
+        Err(ValidationError::new(
+            "length",
+            format!("{} has length {}, {}", field, length, describe_bounds(min, max)),
+        ).with_field(field))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn validate_range<T: PartialOrd + Display>(
+    value: &T,
+    min: Option<T>,
+    max: Option<T>,
+    field: &str,
+) -> Result<(), ValidationError> {
+    let too_small = min.as_ref().map_or(false, |min| value < min);
+    let too_big = max.as_ref().map_or(false, |max| value > max);
+    if too_small || too_big {
+        Err(ValidationError::new(
+            "range",
+            format!("{} is {}, {}", field, value, describe_bounds(min, max)),
+        ).with_field(field))
+    } else {
+        Ok(())
+    }
+}
+
+#[cfg(feature = "regex")]
+thread_local! {
+    // Patterns compiled by earlier checks, a zome only has a few of them
+    static REGEXES: RefCell<HashMap<&'static str, Regex>> = RefCell::new(HashMap::new());
+}
+
+/// Checks `value` against `pattern`, compiled on the first check only.
+/// Needs the `regex` feature. The patterns of `#[derive(Validate)]` are checked
+/// at compile time, so an invalid `pattern` panics.
+#[cfg(feature = "regex")]
+pub fn validate_regex<T: AsRef<str>>(
+    value: &T,
+    pattern: &'static str,
+    field: &str,
+) -> Result<(), ValidationError> {
+    let matches = REGEXES.with(|regexes| {
+        regexes
+            .borrow_mut()
+            .entry(pattern)
+            .or_insert_with(|| Regex::new(pattern).expect("invalid regex in validation rule"))
+            .is_match(value.as_ref())
+    });
+    if matches {
+        Ok(())
+    } else {
+        Err(ValidationError::new(
+            "regex",
+            format!("{} does not match {}", field, pattern),
+        ).with_field(field))
+    }
+}
+
+/// Runs the `regex` rule of `#[derive(Validate)]` through `validate_regex`
+#[cfg(feature = "regex")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! hdk_validate_regex {
+    ($value:expr, $pattern:expr, $field:expr) => (
+        ::hdk::validation::validate_regex($value, $pattern, $field)
+    );
+}
+
+// Without the `regex` feature, the rule fails the build of the zome deriving `Validate`
+#[cfg(not(feature = "regex"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! hdk_validate_regex {
+    ($value:expr, $pattern:expr, $field:expr) => (
+        compile_error!("`#[validate(regex = ..)]` rules need the `regex` feature of the hdk")
+    );
+}
+
+fn describe_bounds<T: Display>(min: Option<T>, max: Option<T>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("expected between {} and {}", min, max),
+        (Some(min), None) => format!("expected at least {}", min),
+        (None, Some(max)) => format!("expected at most {}", max),
+        (None, None) => String::new(),
+    }
+}
+
+// Lets `validations!` run the field rules of entry types implementing `Validate`
+// and skip every other entry type, without requiring an implementation for all of them.
+#[doc(hidden)]
+pub struct FieldRules<'a, T: 'a>(pub &'a T);
+
+#[doc(hidden)]
+pub trait CheckFieldRules {
+    fn check(&self) -> Result<(), Vec<ValidationError>>;
+}
+
+impl<'a, T: Validate> CheckFieldRules for FieldRules<'a, T> {
+    fn check(&self) -> Result<(), Vec<ValidationError>> {
+        self.0.validate()
+    }
+}
+
+#[doc(hidden)]
+pub trait SkipFieldRules {
+    fn check(&self) -> Result<(), Vec<ValidationError>>;
+}
+
+impl<'a, 'b, T> SkipFieldRules for &'b FieldRules<'a, T> {
+    fn check(&self) -> Result<(), Vec<ValidationError>> {
+        Ok(())
+    }
+}
+
+/// Helpers answering provenance questions from the `ValidationData` given to validation callbacks.
+/// The trait is in scope inside `validations!` bodies.
+pub trait ValidationContext {
+    /// The agent that authored the entry being validated, i.e. the first of its sources
+    fn author(&self) -> Option<&HashString>;
+
+    /// Whether `agent` is among the sources that signed the entry
+    fn is_signed_by(&self, agent: &HashString) -> bool;
+
+    /// Whether the author is listed in the member list entry stored at `list_address`.
+    /// That entry must be a JSON array of agent addresses, or an object with such a `members` array.
+    fn author_in(&self, list_address: HashString) -> Result<bool, RibosomeError>;
+}
+
+impl ValidationContext for ValidationData {
+    fn author(&self) -> Option<&HashString> {
+        self.sources.first()
+    }
+
+    fn is_signed_by(&self, agent: &HashString) -> bool {
+        self.sources.contains(agent)
+    }
+
+    fn author_in(&self, list_address: HashString) -> Result<bool, RibosomeError> {
+        let author = match self.author() {
+            Some(author) => author,
+            None => return Ok(false),
+        };
+        let list_entry = get_entry(list_address)?.ok_or(RibosomeError::HashNotFound)?;
+        let members = match ::serde_json::from_str(&list_entry) {
+            Ok(MemberList::Addresses(members)) | Ok(MemberList::Members { members }) => members,
+            Err(err) => {
+                return Err(RibosomeError::RibosomeFailed(format!(
+                    "member list entry is not a list of addresses: {}",
+                    err
+                )))
+            }
+        };
+        Ok(members.contains(author))
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MemberList {
+    Addresses(Vec<HashString>),
+    Members { members: Vec<HashString> },
+}
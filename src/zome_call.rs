@@ -0,0 +1,163 @@
+//! File holding the hooks the zome macros run around every zome function they generate
+
+use globals::{cache_app_globals, current_zome_function, set_current_zome_function};
+use logging::{self, Level};
+use metrics;
+use record;
+use serde::Serialize;
+use std::{
+    any::Any,
+    cell::{Cell, RefCell},
+    panic::{self, AssertUnwindSafe},
+    sync::atomic::{AtomicBool, Ordering},
+};
+use RibosomeError;
+
+/// Called by `zome_functions!` when the zome function `function` starts,
+/// before its input is read
+#[doc(hidden)]
+pub fn enter(function: &'static str, encoded_allocation_of_input: u32) -> ZomeCall {
+    // The globals of the previous call are read again when used, e.g. by `hdk::zome_name()`
+    cache_app_globals(None);
+    set_current_zome_function(Some(function));
+    logging::install();
+    record::zome_call(function, encoded_allocation_of_input);
+    metrics::start(function, encoded_allocation_of_input);
+    ZomeCall { _private: () }
+}
+
+/// Called by the callback macros when the callback `callback` starts, before its input is read
+#[doc(hidden)]
+pub fn enter_callback(callback: &'static str, encoded_allocation_of_input: u32) -> ZomeCall {
+    // As for zome functions, the call globals are those of this call, not of the previous one
+    cache_app_globals(None);
+    logging::install();
+    record::zome_call(callback, encoded_allocation_of_input);
+    ZomeCall { _private: () }
+}
+
+/// The zome function or callback being run, ended when dropped however its body returns,
+/// e.g. early on invalid input or by panicking
+#[doc(hidden)]
+pub struct ZomeCall {
+    _private: (),
+}
+
+impl ZomeCall {
+    /// Called by the zome macros with the output of the function, before it returns
+    pub fn exit<O: Serialize>(self, output: &O) {
+        record::zome_output(output);
+        metrics::finish(output);
+    }
+}
+
+impl Drop for ZomeCall {
+    fn drop(&mut self) {
+        metrics::discard();
+        set_current_zome_function(None);
+    }
+}
+
+/// Name of the zome function being run, `None` outside of the functions
+/// generated by `zome_functions!`, e.g. in validation callbacks
+pub fn current_function() -> Option<&'static str> {
+    current_zome_function()
+}
+
+static PANIC_HOOK_INSTALLED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    // Whether a function generated by the zome macros is running, see `catch_panic`
+    static IN_ZOME_CALL: Cell<bool> = Cell::new(false);
+    // Location of the last panic, as reported by the panic hook
+    static PANIC_LOCATION: RefCell<Option<String>> = RefCell::new(None);
+}
+
+/// Installs a panic hook reporting the message and location of panics to the host
+/// through `debug`, as an error log record, before running the previous hook.
+/// Called by the functions generated by the zome macros; only the first call installs the hook.
+/// The hook is global, so panics outside of those functions, e.g. of the tests running them
+/// natively, are left to the previous hook alone.
+pub fn install_panic_hook() {
+    if PANIC_HOOK_INSTALLED.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let previous_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if !IN_ZOME_CALL.with(|in_zome_call| in_zome_call.get()) {
+            previous_hook(info);
+            return;
+        }
+        let message = panic_message(info.payload());
+        let location = info.location().map(|location| {
+            format!("{}:{}:{}", location.file(), location.line(), location.column())
+        });
+        // Reporting calls the host through the memory stack: without it, e.g. when the
+        // panic comes from reading it, the report would panic again inside the hook
+        if ::memory_stack_ready() {
+            let record = logging::record_json(
+                Level::Error,
+                format!("panicked: {}", message),
+                vec![("location", json!(location))],
+            );
+            // Panics are reported whatever the max log level
+            let _ = ::debug(&record.to_string());
+        }
+        PANIC_LOCATION.with(|last_location| *last_location.borrow_mut() = location);
+        previous_hook(info);
+    }));
+}
+
+/// Runs the body of a function generated by the zome macros, returning the output
+/// `on_panic` makes of the `ZomeFunctionPanicked` error if it panics.
+/// WASM zomes abort on panics, so only the report of the panic hook reaches the host.
+#[doc(hidden)]
+pub fn catch_panic<F, P>(export: F, on_panic: P) -> u32
+where
+    F: FnOnce() -> u32,
+    P: FnOnce(RibosomeError) -> u32,
+{
+    // Restored after the call, as callbacks of other mock agents run inside zome functions
+    let was_in_zome_call = IN_ZOME_CALL.with(|in_zome_call| in_zome_call.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(export));
+    IN_ZOME_CALL.with(|in_zome_call| in_zome_call.set(was_in_zome_call));
+    match result {
+        Ok(encoded_allocation_of_output) => encoded_allocation_of_output,
+        Err(payload) => {
+            on_panic(RibosomeError::ZomeFunctionPanicked {
+                message: panic_message(&*payload),
+                location: PANIC_LOCATION.with(|last_location| last_location.borrow_mut().take()),
+            })
+        }
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Box<Any>".to_string()
+    }
+}
+
+/// Marker the callback macros place in the data of a zome for each callback they generate,
+/// so `hdk::testing::ZomeManifest` can tell callbacks from zome functions in a compiled zome
+#[doc(hidden)]
+#[repr(C)]
+pub struct CallbackMarker {
+    pub magic: [u8; 12],
+    pub callback: extern "C" fn(u32) -> u32,
+}
+
+#[doc(hidden)]
+pub const CALLBACK_MARKER_MAGIC: [u8; 12] = *b"hdk:callback";
+
+/// Keeps the marker of a callback in the compiled zome, however optimized
+#[doc(hidden)]
+pub fn keep_marker(marker: &'static CallbackMarker) {
+    unsafe {
+        ::std::ptr::read_volatile(marker);
+    }
+}
@@ -0,0 +1,86 @@
+#![cfg(feature = "arbitrary")]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+extern crate proptest;
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::{arbitrary, ValidationContext, ValidationData};
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+use proptest::{
+    prelude::*,
+    strategy::ValueTree,
+    test_runner::{TestError, TestRunner},
+};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct Post {
+    content: String,
+}
+
+impl Arbitrary for Post {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Post>;
+
+    fn arbitrary_with(_args: ()) -> Self::Strategy {
+        "[a-c]{0,3}".prop_map(|content| Post { content }).boxed()
+    }
+}
+
+validations! {
+    [ENTRY] validate_post {
+        [hdk::ValidationPackage::Entry]
+        |post: Post, ctx: hdk::ValidationData| {
+            if post.content.is_empty() {
+                Err("posts can not be empty".to_string())
+            } else if !ctx.is_signed_by(&HashString::from("QmMockAgentKey")) {
+                Err("posts must be signed by their author".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+fn signed_by_author(ctx: &ValidationData) -> bool {
+    ctx.is_signed_by(&HashString::from(arbitrary::AGENT_KEY_HASHES[0]))
+}
+
+#[test]
+fn validation_agrees_with_expected_rules() {
+    arbitrary::check_entry_validation(validate_post, |post: &Post, ctx| {
+        !post.content.is_empty() && signed_by_author(ctx)
+    }).unwrap();
+}
+
+#[test]
+fn reports_simplest_inconsistent_entry() {
+    let result = arbitrary::check_entry_validation(validate_post, |_post: &Post, ctx| {
+        signed_by_author(ctx)
+    });
+    match result {
+        Err(TestError::Fail(_, (post, ctx))) => {
+            assert_eq!(post.content, "");
+            assert!(signed_by_author(&ctx));
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn can_run_validation_with_generated_data() {
+    hdk::mock::reset();
+    let ctx = arbitrary::validation_data()
+        .new_tree(&mut TestRunner::default())
+        .unwrap()
+        .current();
+    let post = Post {
+        content: String::new(),
+    };
+    let errors = arbitrary::run_entry_validation(validate_post, &post, &ctx).unwrap_err();
+    assert_eq!(errors[0].message, "posts can not be empty");
+}
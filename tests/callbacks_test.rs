@@ -0,0 +1,150 @@
+#![cfg(feature = "mock")]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+#[macro_use]
+extern crate log;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::{BridgeSide, EntryType};
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+
+genesis! {
+    || {
+        hdk::commit_entry("handle", json!({ "name": "alex" }))
+            .map(|_| ())
+            .map_err(|err| format!("could not commit handle: {}", err))
+    }
+}
+
+bridge_genesis! {
+    |side: hdk::BridgeSide, dna_hash: HashString| {
+        assert!(dna_hash != HashString::from("QmBroken"), "broken DNA");
+        if side == BridgeSide::To && dna_hash != HashString::from("QmTrusted") {
+            warn!(target: "bridges", "refused {}", dna_hash);
+            Err(format!("{} can not call this DNA", dna_hash))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+zome_functions! {
+    call_details: | | {
+        json!({
+            "provenance": hdk::call_provenance().unwrap(),
+            "capability": hdk::capability_name().unwrap(),
+            "timestamp": hdk::call_timestamp().unwrap(),
+        })
+    }
+}
+
+receive! {
+    |from: HashString, message: String| {
+        json!({
+            "from": from,
+            "message": message,
+            "provenance": hdk::call_provenance().unwrap(),
+            "capability": hdk::capability_name().unwrap(),
+            "timestamp": hdk::call_timestamp().unwrap(),
+        })
+    }
+}
+
+#[test]
+fn genesis_succeeds_without_message() {
+    hdk::mock::reset();
+    assert_eq!(hdk::mock::call(genesis, json!({})), Ok(String::new()));
+    hdk::mock::with_host(|host| {
+        assert_eq!(host.chain().len(), 1);
+        assert_eq!(host.chain()[0].header.entry_type, EntryType::from("handle"));
+    });
+}
+
+#[test]
+fn genesis_fails_with_its_message() {
+    hdk::mock::reset();
+    hdk::mock::expect_commit().returns_err("chain is full");
+    let output = hdk::mock::call(genesis, json!({})).unwrap();
+    let message: String = serde_json::from_str(&output).unwrap();
+    assert_eq!(message, "could not commit handle: chain is full");
+    hdk::mock::with_host(|host| assert!(host.chain().is_empty()));
+    hdk::mock::verify();
+}
+
+#[test]
+fn bridge_genesis_receives_the_side_and_dna() {
+    hdk::mock::reset();
+    let input = json!({ "side": "From", "dna_hash": "QmOther" });
+    assert_eq!(hdk::mock::call(bridge_genesis, input), Ok(String::new()));
+    let input = json!({ "side": "To", "dna_hash": "QmTrusted" });
+    assert_eq!(hdk::mock::call(bridge_genesis, input), Ok(String::new()));
+}
+
+#[test]
+fn bridge_genesis_refuses_with_its_message() {
+    hdk::mock::reset();
+    let input = json!({ "side": "To", "dna_hash": "QmOther" });
+    let output = hdk::mock::call(bridge_genesis, input).unwrap();
+    let message: String = serde_json::from_str(&output).unwrap();
+    assert_eq!(message, "QmOther can not call this DNA");
+}
+
+#[test]
+fn log_crate_records_are_forwarded_from_callbacks() {
+    hdk::mock::reset();
+    let input = json!({ "side": "To", "dna_hash": "QmOther" });
+    hdk::mock::call(bridge_genesis, input).unwrap();
+    hdk::mock::with_host(|host| {
+        let record: serde_json::Value = serde_json::from_str(&host.debug_log()[0]).unwrap();
+        assert_eq!(record["level"], "warn");
+        assert_eq!(record["message"], "refused QmOther");
+        assert_eq!(record["fields"], json!({ "target": "bridges" }));
+    });
+}
+
+#[test]
+fn bridge_genesis_refuses_when_it_panics() {
+    hdk::mock::reset();
+    let input = json!({ "side": "From", "dna_hash": "QmBroken" });
+    let output = hdk::mock::call(bridge_genesis, input).unwrap();
+    let message: String = serde_json::from_str(&output).unwrap();
+    assert!(message.starts_with("Zome function panicked at tests/callbacks_test.rs:"));
+    assert!(message.ends_with(": broken DNA"));
+}
+
+#[test]
+fn receive_reads_the_globals_of_its_own_call() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| {
+        host.set_global("capability_name", "admin");
+        host.set_global("timestamp", 1000);
+    });
+    let output = hdk::mock::call(call_details, json!({})).unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(output, json!({ "provenance": "Local", "capability": "admin", "timestamp": 1000 }));
+
+    hdk::mock::with_host(|host| {
+        host.set_global("call_provenance", hdk::CallProvenance::Remote(HashString::from("QmBob")));
+        host.remove_global("capability_name");
+        host.set_global("timestamp", 2000);
+    });
+    let input = json!({ "from": "QmBob", "message": "hello" });
+    let output = hdk::mock::call(receive, input).unwrap();
+    let output: serde_json::Value = serde_json::from_str(&output).unwrap();
+    assert_eq!(
+        output,
+        json!({
+            "from": "QmBob",
+            "message": "hello",
+            "provenance": { "Remote": "QmBob" },
+            "capability": null,
+            "timestamp": 2000,
+        })
+    );
+}
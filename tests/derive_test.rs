@@ -0,0 +1,120 @@
+extern crate hdk;
+#[macro_use]
+extern crate hdk_derive;
+
+use hdk::{Validate, ValidationError};
+
+#[derive(Validate)]
+struct Post {
+    #[validate(non_empty, length(max = 10))]
+    content: String,
+    #[validate(range(min = -2, max = 5))]
+    rating: i8,
+    #[validate(length(min = 1))]
+    tags: Vec<String>,
+}
+
+#[cfg(feature = "regex")]
+#[derive(Validate)]
+struct Author {
+    #[validate(non_empty, regex = "^[a-z]+$")]
+    name: String,
+}
+
+fn valid_post() -> Post {
+    Post {
+        content: "hello".to_string(),
+        rating: 3,
+        tags: vec!["news".to_string()],
+    }
+}
+
+fn failed_codes<T: Validate>(entry: &T) -> Vec<(String, Option<String>)> {
+    entry
+        .validate()
+        .unwrap_err()
+        .into_iter()
+        .map(|error| (error.code, error.field))
+        .collect()
+}
+
+fn failure(code: &str, field: &str) -> (String, Option<String>) {
+    (code.to_string(), Some(field.to_string()))
+}
+
+#[test]
+fn valid_entries_pass_every_rule() {
+    assert_eq!(valid_post().validate(), Ok(()));
+    let post = Post { rating: -2, ..valid_post() };
+    assert_eq!(post.validate(), Ok(()));
+}
+
+#[test]
+fn non_empty_and_length_rules() {
+    let post = Post { content: String::new(), ..valid_post() };
+    assert_eq!(failed_codes(&post), vec![failure("non_empty", "content")]);
+
+    let post = Post { content: "hello world".to_string(), ..valid_post() };
+    assert_eq!(
+        post.validate(),
+        Err(vec![
+            ValidationError::new("length", "content has length 11, expected at most 10")
+                .with_field("content"),
+        ])
+    );
+
+    let post = Post { tags: vec![], ..valid_post() };
+    assert_eq!(failed_codes(&post), vec![failure("length", "tags")]);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn regex_rule() {
+    let author = Author { name: "Alice".to_string() };
+    assert_eq!(
+        author.validate(),
+        Err(vec![
+            ValidationError::new("regex", "name does not match ^[a-z]+$").with_field("name"),
+        ])
+    );
+    // The compiled pattern is reused by later checks
+    assert_eq!(Author { name: "alice".to_string() }.validate(), Ok(()));
+    assert_eq!(failed_codes(&author), vec![failure("regex", "name")]);
+
+    let author = Author { name: String::new() };
+    assert_eq!(
+        failed_codes(&author),
+        vec![failure("non_empty", "name"), failure("regex", "name")]
+    );
+}
+
+#[test]
+fn range_rule_accepts_negative_bounds() {
+    let post = Post { rating: -3, ..valid_post() };
+    assert_eq!(
+        post.validate(),
+        Err(vec![
+            ValidationError::new("range", "rating is -3, expected between -2 and 5")
+                .with_field("rating"),
+        ])
+    );
+    let post = Post { rating: 6, ..valid_post() };
+    assert_eq!(failed_codes(&post), vec![failure("range", "rating")]);
+}
+
+#[test]
+fn every_failed_rule_is_reported() {
+    let post = Post {
+        content: String::new(),
+        rating: 9,
+        tags: vec![],
+    };
+    assert_eq!(
+        failed_codes(&post),
+        vec![
+            failure("non_empty", "content"),
+            failure("range", "rating"),
+            failure("length", "tags"),
+        ]
+    );
+}
@@ -1,8 +1,14 @@
+extern crate hdk;
 extern crate holochain_core;
 extern crate holochain_core_api;
 extern crate holochain_dna;
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate serde_json;
 extern crate test_utils;
 
+use hdk::testing::{decode_output, input_json, ZomeManifest};
 use holochain_core_api::*;
 use holochain_dna::zome::capabilities::{Capability, FnDeclaration};
 use std::sync::{Arc, Mutex};
@@ -23,13 +29,8 @@ fn start_holochain_instance() -> (Holochain, Arc<Mutex<TestLogger>>) {
     // Setup the holochain instance
     let wasm =
         create_wasm_from_file("wasm-test/target/wasm32-unknown-unknown/release/test_globals.wasm");
-    let capabability = create_test_cap_with_fn_names(vec![
-        "check_global",
-        "check_commit_entry",
-        "check_commit_entry_macro",
-        "check_get_entry",
-        "send_tweet",
-    ]);
+    let manifest = ZomeManifest::from_wasm(&wasm).expect("test zome should be a WASM module");
+    let capabability = create_test_cap_with_fn_names(manifest.function_names());
     let dna = create_test_dna_with_cap("test_zome", "test_cap", &capabability, &wasm);
 
     let (context, test_logger) = test_context_and_logger("alex");
@@ -48,48 +49,104 @@ fn can_use_globals() {
     assert!(!result.clone().unwrap().is_empty(), "result = {:?}", result);
 }
 
+#[test]
+fn can_try_globals() {
+    let (mut hc, _) = start_holochain_instance();
+    let result = hc.call("test_zome", "test_cap", "check_try_globals", r#"{}"#);
+    assert!(result.is_ok(), "result = {:?}", result);
+    let result = result.unwrap();
+    assert!(result.starts_with(r#"{"app_name":"#), "result = {:?}", result);
+}
+
+#[test]
+fn can_get_agent_id() {
+    let (mut hc, _) = start_holochain_instance();
+    let result = hc.call("test_zome", "test_cap", "check_agent_id", r#"{}"#);
+    assert!(result.is_ok(), "result = {:?}", result);
+    let result = result.unwrap();
+    assert!(result.contains(r#""identity":"alex""#), "result = {:?}", result);
+}
+
+#[derive(Serialize)]
+struct CommitInput {
+    entry_type_name: String,
+    entry_content: String,
+}
+
+#[derive(Deserialize)]
+struct CommitOutput {
+    address: String,
+}
+
+#[derive(Serialize)]
+struct GetEntryInput {
+    entry_hash: String,
+}
+
+// Commits a testEntryType entry through `function`, and returns its address
+fn commit_test_entry(hc: &mut Holochain, function: &str, stuff: &str) -> String {
+    let input = CommitInput {
+        entry_type_name: "testEntryType".to_string(),
+        entry_content: json!({ "stuff": stuff }).to_string(),
+    };
+    let result = hc.call("test_zome", "test_cap", function, &input_json(&input));
+    assert!(result.is_ok(), "\t result = {:?}", result);
+    let output: CommitOutput = decode_output(&result.unwrap()).unwrap();
+    output.address
+}
+
+fn get_test_entry(hc: &mut Holochain, address: &str) -> String {
+    let input = GetEntryInput {
+        entry_hash: address.to_string(),
+    };
+    let result = hc.call("test_zome", "test_cap", "check_get_entry", &input_json(&input));
+    assert!(result.is_ok(), "\t result = {:?}", result);
+    result.unwrap()
+}
+
 #[test]
 fn can_commit_entry() {
     let (mut hc, _) = start_holochain_instance();
     // Call the exposed wasm function that calls the Commit API function
-    let result = hc.call(
-        "test_zome",
-        "test_cap",
-        "check_commit_entry",
-        r#"{ "entry_type_name": "testEntryType", "entry_content": "{\"stuff\": \"non fail\"}" }"#,
-    );
-    println!("\t result = {:?}", result);
-    assert!(result.is_ok(), "result = {:?}", result);
-    assert_eq!(result.unwrap(),r#"{"address":"QmZi7c1G2qAN6Y5wxHDB9fLhSaSVBJe28ZVkiPraLEcvou"}"#);
+    let address = commit_test_entry(&mut hc, "check_commit_entry", "non fail");
+    assert_eq!(get_test_entry(&mut hc, &address), r#"{"stuff":"non fail"}"#);
 }
 
 #[test]
 fn can_commit_entry_macro() {
     let (mut hc, _) = start_holochain_instance();
     // Call the exposed wasm function that calls the Commit API function
-    let result = hc.call(
-        "test_zome",
-        "test_cap",
-        "check_commit_entry_macro",
-        r#"{ "entry_type_name": "testEntryType", "entry_content": "{\"stuff\": \"non fail\"}" }"#,
-    );
-    println!("\t result = {:?}", result);
-    assert!(result.is_ok(), "\t result = {:?}", result);
-    assert_eq!(result.unwrap(),r#"{"address":"QmZi7c1G2qAN6Y5wxHDB9fLhSaSVBJe28ZVkiPraLEcvou"}"#);
+    let address = commit_test_entry(&mut hc, "check_commit_entry_macro", "non fail");
+    assert_eq!(get_test_entry(&mut hc, &address), r#"{"stuff":"non fail"}"#);
+}
+
+#[derive(Serialize)]
+struct TweetInput {
+    author: String,
+    content: String,
+}
+
+#[derive(Deserialize, Debug, PartialEq)]
+struct TweetResponse {
+    first: String,
+    second: String,
 }
 
 #[test]
 fn can_round_trip() {
     let (mut hc, test_logger) = start_holochain_instance();
-    let result = hc.call(
-        "test_zome",
-        "test_cap",
-        "send_tweet",
-        r#"{ "author": "bob", "content": "had a boring day" }"#,
-    );
+    let input = TweetInput {
+        author: "bob".to_string(),
+        content: "had a boring day".to_string(),
+    };
+    let result = hc.call("test_zome", "test_cap", "send_tweet", &input_json(&input));
+    let response: TweetResponse = decode_output(&result.unwrap()).unwrap();
     assert_eq!(
-        result.unwrap(),
-        "{\"first\":\"bob\",\"second\":\"had a boring day\"}"
+        response,
+        TweetResponse {
+            first: "bob".to_string(),
+            second: "had a boring day".to_string(),
+        }
     );
 
     let test_logger = test_logger.lock().unwrap();
@@ -100,36 +157,15 @@ fn can_round_trip() {
 #[test]
 fn can_get_entry() {
     let (mut hc, _) = start_holochain_instance();
-    // Call the exposed wasm function that calls the Commit API function
-    let result = hc.call(
-        "test_zome",
-        "test_cap",
-        "check_commit_entry_macro",
-        r#"{ "entry_type_name": "testEntryType", "entry_content": "{\"stuff\": \"non fail\"}" }"#,
-    );
-    assert!(result.is_ok(), "\t result = {:?}", result);
-    assert_eq!(result.unwrap(),"{\"address\":\"QmZi7c1G2qAN6Y5wxHDB9fLhSaSVBJe28ZVkiPraLEcvou\"}");
-
-    let result = hc.call(
-        "test_zome",
-        "test_cap",
-        "check_get_entry",
-        r#"{"entry_hash":"QmZi7c1G2qAN6Y5wxHDB9fLhSaSVBJe28ZVkiPraLEcvou"}"#,
-    );
+    let address = commit_test_entry(&mut hc, "check_commit_entry_macro", "non fail");
+    let result = get_test_entry(&mut hc, &address);
     println!("\t can_get_entry result = {:?}", result);
-    assert!(result.is_ok(), "\t result = {:?}", result);
-    assert_eq!(result.unwrap(),"{\"stuff\":\"non fail\"}");
+    assert_eq!(result, r#"{"stuff":"non fail"}"#);
 
     // test the case with a bad hash
-    let result = hc.call(
-        "test_zome",
-        "test_cap",
-        "check_get_entry",
-        r#"{"entry_hash":"QmbC71ggSaEa1oVPTeNN7ZoB93DYhxowhKSF6Yia2Vjxxx"}"#,
-    );
+    let result = get_test_entry(&mut hc, "QmbC71ggSaEa1oVPTeNN7ZoB93DYhxowhKSF6Yia2Vjxxx");
     println!("\t can_get_entry result = {:?}", result);
-    assert!(result.is_ok(), "\t result = {:?}", result);
-    assert_eq!(result.unwrap(),"{\"got back no entry\":true}");
+    assert_eq!(result, r#"{"got back no entry":true}"#);
 }
 
 #[test]
@@ -144,5 +180,41 @@ fn can_invalidate_invalid_commit() {
     );
     println!("\t result = {:?}", result);
     assert!(result.is_ok(), "\t result = {:?}", result);
-    assert_eq!("{\"validation failed\":\"\\\"FAIL content is not allowed\\\"\"}", result.unwrap());
+    assert_eq!(
+        r#"{"validation failed":[{"code":"custom","message":"FAIL content is not allowed"}]}"#,
+        result.unwrap()
+    );
+}
+
+fn commit_post(hc: &mut Holochain, post: &str) -> String {
+    let input = json!({ "entry_type_name": "testPostType", "entry_content": post });
+    let result = hc.call("test_zome", "test_cap", "check_commit_entry_macro", &input.to_string());
+    assert!(result.is_ok(), "\t result = {:?}", result);
+    result.unwrap()
+}
+
+#[test]
+fn can_commit_entry_passing_field_rules() {
+    let (mut hc, _) = start_holochain_instance();
+    let result = commit_post(&mut hc, r#"{"content":"hello","author":"alex","rating":5}"#);
+    assert!(result.starts_with(r#"{"address":"#), "\t result = {:?}", result);
+}
+
+#[test]
+fn can_invalidate_commit_failing_field_rules() {
+    let (mut hc, _) = start_holochain_instance();
+    let failures = vec![
+        (json!({ "content": "", "author": "alex", "rating": 1 }), "non_empty", "content"),
+        (json!({ "content": "a".repeat(281), "author": "alex", "rating": 1 }), "length", "content"),
+        (json!({ "content": "hello", "author": "Alex", "rating": 1 }), "regex", "author"),
+        (json!({ "content": "hello", "author": "alex", "rating": 0 }), "range", "rating"),
+        (json!({ "content": "hello", "author": "alex", "rating": 6 }), "range", "rating"),
+    ];
+    for (post, code, field) in failures {
+        let result = commit_post(&mut hc, &post.to_string());
+        let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+        let errors = &result["validation failed"];
+        assert_eq!(errors[0]["code"], code, "\t result = {:?}", result);
+        assert_eq!(errors[0]["field"], field, "\t result = {:?}", result);
+    }
 }
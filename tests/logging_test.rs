@@ -0,0 +1,106 @@
+#![cfg(feature = "mock")]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+#[macro_use]
+extern crate log;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::logging::{self, Level};
+use serde_json::Value;
+
+zome_functions! {
+    create_post: |content: String| {
+        let post_hash = hdk::commit_entry("post", json!({ "content": content })).unwrap();
+        hdk_info!(address = post_hash, length = content.len(); "created post");
+        hdk_trace!("post content: {}", content);
+        post_hash
+    }
+
+    parse_post: |content: String| {
+        // Stands for a library logging through the log crate
+        warn!(target: "post_parser", "could not parse {}", content);
+        content.len()
+    }
+}
+
+fn log_records() -> Vec<Value> {
+    hdk::mock::with_host(|host| {
+        host.debug_log()
+            .iter()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    })
+}
+
+#[test]
+fn records_hold_the_zome_function_and_fields() {
+    hdk::mock::reset();
+    let output = hdk::mock::call(create_post, json!({ "content": "hi" })).unwrap();
+    let address: Value = serde_json::from_str(&output).unwrap();
+
+    let records = log_records();
+    assert_eq!(
+        records[0],
+        json!({
+            "level": "info",
+            "zome": "mock_zome",
+            "function": "create_post",
+            "message": "created post",
+            "fields": { "address": address, "length": 2 },
+        })
+    );
+    if logging::enabled(Level::Trace) {
+        assert_eq!(records[1]["level"], "trace");
+        assert_eq!(records[1]["message"], "post content: hi");
+        assert_eq!(records[1]["fields"], json!({}));
+    } else {
+        assert_eq!(records.len(), 1);
+    }
+    assert_eq!(hdk::zome_call::current_function(), None);
+}
+
+#[test]
+fn records_outside_zome_functions_have_no_function() {
+    hdk::mock::reset();
+    hdk_warn!("no zome function");
+    hdk_error!(code = 42; "failed with {}", "an error");
+
+    let records = log_records();
+    assert_eq!(records[0]["level"], "warn");
+    assert_eq!(records[0]["function"], Value::Null);
+    assert_eq!(records[1]["level"], "error");
+    assert_eq!(records[1]["message"], "failed with an error");
+    assert_eq!(records[1]["fields"], json!({ "code": 42 }));
+}
+
+#[test]
+fn log_crate_records_are_forwarded_from_zome_functions() {
+    hdk::mock::reset();
+    hdk::mock::call(parse_post, json!({ "content": "hi" })).unwrap();
+
+    let records = log_records();
+    assert_eq!(
+        records[0],
+        json!({
+            "level": "warn",
+            "zome": "mock_zome",
+            "function": "parse_post",
+            "message": "could not parse hi",
+            "fields": { "target": "post_parser" },
+        })
+    );
+}
+
+#[test]
+fn levels_are_ordered_by_verbosity() {
+    assert!(Level::Error < Level::Warn);
+    assert!(Level::Debug < Level::Trace);
+    assert_eq!(Level::Info.to_string(), "info");
+    assert!(logging::enabled(Level::Error));
+}
@@ -0,0 +1,83 @@
+#![cfg(all(feature = "mock", feature = "metrics"))]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::metrics::ZomeCallMetrics;
+
+zome_functions! {
+    create_post: |content: String| {
+        let post_hash = hdk::commit_entry("post", json!({ "content": content })).unwrap();
+        let anchor_hash = hdk::commit_entry("anchor", json!("all_posts")).unwrap();
+        hdk::link_entries(anchor_hash, post_hash.clone(), "posts").unwrap();
+        post_hash
+    }
+}
+
+fn metrics_log() -> Vec<ZomeCallMetrics> {
+    hdk::mock::with_host(|host| {
+        host.debug_log()
+            .iter()
+            .filter_map(|line| ZomeCallMetrics::from_log_line(line))
+            .collect()
+    })
+}
+
+#[test]
+fn reports_host_calls_of_each_zome_function_call() {
+    hdk::mock::reset();
+    let output = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    hdk::mock::call(create_post, json!({ "content": "world" })).unwrap();
+
+    let metrics = metrics_log();
+    assert_eq!(metrics.len(), 2);
+    let first = &metrics[0];
+    assert_eq!(first.zome, Some("mock_zome".to_string()));
+    assert_eq!(first.function, "create_post");
+    assert_eq!(first.input_bytes, r#"{"content":"hello"}"#.len());
+    assert_eq!(first.output_bytes, output.len());
+
+    let commits = &first.host_calls["hc_commit_entry"];
+    assert_eq!(commits.calls, 2);
+    assert!(commits.input_bytes > 0 && commits.output_bytes > 0);
+    assert_eq!(first.host_calls["hc_link_entries"].calls, 1);
+    assert_eq!(first.host_calls["hc_init_globals"].calls, 1);
+    assert_eq!(first.host_call_count(), 4);
+    // Only the first call reads the globals, to check the API version
+    assert_eq!(metrics[1].host_call_count(), 3);
+
+    let largest_call = first
+        .host_calls
+        .values()
+        .map(|host_call| host_call.input_bytes + host_call.output_bytes)
+        .max()
+        .unwrap();
+    assert!(first.peak_stack_bytes > first.input_bytes);
+    assert!(first.peak_stack_bytes <= first.input_bytes + largest_call);
+}
+
+#[test]
+fn calls_outside_zome_functions_are_not_reported() {
+    hdk::mock::reset();
+    hdk::commit_entry("post", json!({ "content": "hello" })).unwrap();
+    assert_eq!(metrics_log(), vec![]);
+}
+
+#[test]
+fn calls_returning_early_are_not_reported() {
+    hdk::mock::reset();
+    hdk::mock::call(create_post, json!({ "content": 42 })).unwrap_err();
+    hdk::commit_entry("post", json!({ "content": "hello" })).unwrap();
+    assert_eq!(metrics_log(), vec![]);
+
+    hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    let metrics = metrics_log();
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].host_calls["hc_commit_entry"].calls, 2);
+}
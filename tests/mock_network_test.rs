@@ -0,0 +1,132 @@
+#![cfg(feature = "mock")]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::mock::Harness;
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+
+#[derive(Serialize, Deserialize)]
+struct Post {
+    content: String,
+}
+
+fn all_posts() -> HashString {
+    hdk::commit_entry("anchor", json!("all_posts")).unwrap()
+}
+
+zome_functions! {
+    create_post: |content: String| {
+        let post_hash = hdk::commit_entry("post", json!({ "content": content })).unwrap();
+        hdk::link_entries(all_posts(), post_hash.clone(), "posts").unwrap();
+        post_hash
+    }
+
+    list_posts: | | {
+        let posts: Vec<Post> = hdk::get_links_and_load(all_posts(), "posts")
+            .unwrap()
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+        posts.into_iter().map(|post| post.content).collect::<Vec<String>>()
+    }
+
+    ping: |to: HashString| {
+        match hdk::send(to, json!("hello")) {
+            Ok(response) => response,
+            Err(err) => err.to_json(),
+        }
+    }
+
+    agent_name: | | {
+        hdk::try_globals().unwrap().app_agent_id_str.clone()
+    }
+}
+
+validations! {
+    [ENTRY] validate_post {
+        [hdk::ValidationPackage::Entry]
+        |post: Post, _ctx: hdk::ValidationData| {
+            if post.content == "spam" {
+                Err("spam is not allowed".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+receive! {
+    |from: HashString, message: String| {
+        let globals = hdk::try_globals().unwrap();
+        json!({ "echo": message, "from": from, "to": globals.app_agent_id_str })
+    }
+}
+
+#[test]
+fn other_agents_see_links_after_sync() {
+    let harness = Harness::new(&["alice", "bob"]);
+    harness.call("alice", create_post, json!({ "content": "hi" })).unwrap();
+    assert_eq!(harness.call("alice", list_posts, json!({})).unwrap(), r#"["hi"]"#);
+    assert_eq!(harness.call("bob", list_posts, json!({})).unwrap(), "[]");
+
+    assert_eq!(harness.sync(), vec![]);
+    assert_eq!(harness.call("bob", list_posts, json!({})).unwrap(), r#"["hi"]"#);
+
+    let links = harness
+        .as_agent("bob", || {
+            hdk::get_links_with_options(all_posts(), "posts", Default::default())
+        })
+        .unwrap();
+    assert_eq!(links.links[0].author, harness.key_hash("alice"));
+}
+
+#[test]
+fn other_agents_validate_gossiped_entries() {
+    let harness = Harness::new(&["alice", "bob", "carol"]);
+    harness.validation("post", validate_post);
+    let spam = harness.call("alice", create_post, json!({ "content": "spam" })).unwrap();
+    let spam: HashString = serde_json::from_str(&spam).unwrap();
+
+    let rejections = harness.sync();
+    assert_eq!(rejections.len(), 2);
+    assert_eq!(rejections[0].agent, "bob");
+    assert_eq!(rejections[0].address, spam);
+    assert_eq!(rejections[0].errors[0].message, "spam is not allowed");
+    assert_eq!(harness.as_agent("carol", || hdk::get_entry(spam.clone())).unwrap(), None);
+    assert!(harness.as_agent("alice", || hdk::get_entry(spam)).unwrap().is_some());
+}
+
+#[test]
+fn send_reaches_the_receive_callback() {
+    let harness = Harness::new(&["alice", "bob"]);
+    harness.receive(receive);
+    let bob = harness.key_hash("bob");
+    let response = harness.call("alice", ping, json!({ "to": bob })).unwrap();
+    let response: serde_json::Value = serde_json::from_str(&response).unwrap();
+    assert_eq!(
+        response,
+        json!({ "echo": "hello", "from": harness.key_hash("alice"), "to": "bob" })
+    );
+    assert_eq!(harness.call("alice", agent_name, json!({})).unwrap(), r#""alice""#);
+
+    let response = harness.call("alice", ping, json!({ "to": "QmNobody" })).unwrap();
+    assert!(response.contains("can not be reached"));
+}
+
+#[test]
+fn as_agent_switches_back_when_it_panics() {
+    let harness = Harness::new(&["alice", "bob"]);
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        harness.as_agent("bob", || panic!("bob failed"))
+    }));
+    assert!(result.is_err());
+    assert_eq!(hdk::try_globals().unwrap().app_agent_id_str, "alice");
+    assert_eq!(harness.call("bob", agent_name, json!({})).unwrap(), r#""bob""#);
+}
@@ -0,0 +1,632 @@
+#![cfg(feature = "mock")]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::{
+    Bridge, BridgeSide, EntryType, GetLinksOptions, LinkSortOrder, RibosomeError, SystemEntryType,
+};
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+
+#[derive(Serialize, Deserialize)]
+struct Post {
+    content: String,
+}
+
+zome_functions! {
+    create_post: |content: String| {
+        let post_hash = hdk::commit_entry("post", json!({ "content": content })).unwrap();
+        let agent_hash = hdk::commit_entry("agent_anchor", json!({ "agent": "mock" })).unwrap();
+        hdk::link_entries(agent_hash, post_hash.clone(), "authored_posts").unwrap();
+        post_hash
+    }
+
+    posts_by_agent: | | {
+        let agent_hash = hdk::commit_entry("agent_anchor", json!({ "agent": "mock" })).unwrap();
+        let posts: Vec<Post> = hdk::get_links_and_load(agent_hash, "authored_posts")
+            .unwrap()
+            .into_iter()
+            .filter_map(Result::ok)
+            .collect();
+        posts.into_iter().map(|post| post.content).collect::<Vec<String>>()
+    }
+
+    check_get_entry: |entry_hash: HashString| {
+        match hdk::get_entry(entry_hash) {
+            Ok(Some(entry)) => json!({ "entry": entry }),
+            Ok(None) => json!({ "got back no entry": true }),
+            Err(err) => err.to_json(),
+        }
+    }
+
+    app_name: | | {
+        hdk::debug("reading app name").unwrap();
+        hdk::try_globals().unwrap().app_name.clone()
+    }
+
+    call_details: | | {
+        json!({
+            "zome": hdk::zome_name().unwrap(),
+            "capability": hdk::capability_name().unwrap(),
+            "timestamp": hdk::call_timestamp().unwrap(),
+        })
+    }
+
+    get_post: |post_hash: HashString| {
+        let post = hdk::get_entry(post_hash).unwrap().expect("post should exist");
+        serde_json::from_str::<Post>(&post).unwrap().content
+    }
+
+    check_chain: | | {
+        hdk::mock::with_host(|host| assert!(!host.chain().is_empty(), "chain should not be empty"))
+    }
+}
+
+#[test]
+fn can_commit_and_get_entry() {
+    hdk::mock::reset();
+    let post_hash = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    let post_hash: HashString = serde_json::from_str(&post_hash).unwrap();
+
+    let result = hdk::mock::call(check_get_entry, json!({ "entry_hash": post_hash })).unwrap();
+    assert_eq!(result, r#"{"entry":"{\"content\":\"hello\"}"}"#);
+
+    let result = hdk::mock::call(check_get_entry, json!({ "entry_hash": "QmUnknown" })).unwrap();
+    assert_eq!(result, r#"{"got back no entry":true}"#);
+
+    hdk::mock::with_host(|host| {
+        assert_eq!(host.chain().len(), 2);
+        assert_eq!(host.chain()[0].header.entry_type, EntryType::from("post"));
+        assert_eq!(host.chain()[1].header.link_same_type, None);
+    });
+}
+
+#[test]
+fn can_get_links_and_load() {
+    hdk::mock::reset();
+    hdk::mock::call(create_post, json!({ "content": "first" })).unwrap();
+    hdk::mock::call(create_post, json!({ "content": "second" })).unwrap();
+    let result = hdk::mock::call(posts_by_agent, json!({})).unwrap();
+    assert_eq!(result, r#"["first","second"]"#);
+}
+
+#[test]
+fn can_page_through_links() {
+    hdk::mock::reset();
+    let base = hdk::mock::with_host(|host| {
+        let base = host.put_entry(EntryType::from("anchor"), "{}");
+        for i in 0..5 {
+            let target = host.put_entry(EntryType::from("post"), &i.to_string());
+            host.add_link(base.clone(), target, "posts", HashString::from("QmOtherAgent"));
+        }
+        base
+    });
+
+    let options = GetLinksOptions {
+        limit: 2,
+        sort: LinkSortOrder::TimestampDescending,
+        ..Default::default()
+    };
+    let first_page = hdk::get_links_with_options(base.clone(), "posts", options.clone()).unwrap();
+    assert_eq!(first_page.links.len(), 2);
+    assert!(first_page.links[0].timestamp > first_page.links[1].timestamp);
+
+    let options = GetLinksOptions {
+        page_token: first_page.next_page_token,
+        ..options
+    };
+    let second_page = hdk::get_links_with_options(base.clone(), "posts", options).unwrap();
+    assert_eq!(second_page.links.len(), 2);
+    assert!(second_page.next_page_token.is_some());
+    assert_eq!(hdk::get_links(base, "posts").unwrap().len(), 5);
+}
+
+fn link_page(targets: &[&str], next_page_token: Option<&str>) -> serde_json::Value {
+    let links: Vec<serde_json::Value> = targets
+        .iter()
+        .map(|target| {
+            json!({
+                "target": target,
+                "author": "QmOtherAgent",
+                "timestamp": "2018-10-01T00:00:00Z",
+                "status": 1,
+                "entry": json!({ "content": target }).to_string(),
+            })
+        })
+        .collect();
+    json!({ "links": links, "next_page_token": next_page_token })
+}
+
+#[test]
+fn get_links_gets_every_page() {
+    hdk::mock::reset();
+    hdk::mock::expect_get_links().returns(link_page(&["QmFirst", "QmSecond"], Some("page-2")));
+    hdk::mock::expect_get_links().returns(link_page(&["QmThird"], None));
+
+    let targets = hdk::get_links(HashString::from("QmBase"), "posts").unwrap();
+    assert_eq!(
+        targets,
+        vec![
+            HashString::from("QmFirst"),
+            HashString::from("QmSecond"),
+            HashString::from("QmThird"),
+        ]
+    );
+    let calls = hdk::mock::calls_to("hc_get_links");
+    assert_eq!(calls[0].input["options"]["page_token"], serde_json::Value::Null);
+    assert_eq!(calls[1].input["options"]["page_token"], "page-2");
+    hdk::mock::verify();
+}
+
+#[test]
+fn get_links_fails_on_a_repeated_or_empty_page_token() {
+    hdk::mock::reset();
+    hdk::mock::expect_get_links().returns(link_page(&["QmFirst"], Some("page-2")));
+    hdk::mock::expect_get_links().returns(link_page(&["QmSecond"], Some("page-2")));
+    match hdk::get_links(HashString::from("QmBase"), "posts") {
+        Err(RibosomeError::RibosomeFailed(message)) => assert_eq!(
+            message,
+            r#"invalid next page token of the links with tag posts: "page-2""#
+        ),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    assert_eq!(hdk::mock::calls_to("hc_get_links").len(), 2);
+
+    hdk::mock::expect_get_links().returns(link_page(&["QmFirst"], Some("")));
+    assert!(hdk::get_links_and_load::<Post, _>(HashString::from("QmBase"), "posts").is_err());
+    assert_eq!(hdk::mock::calls_to("hc_get_links").len(), 3);
+    hdk::mock::verify();
+}
+
+#[test]
+fn can_query_and_get_headers() {
+    hdk::mock::reset();
+    hdk::mock::call(create_post, json!({ "content": "first" })).unwrap();
+    hdk::mock::call(create_post, json!({ "content": "second" })).unwrap();
+
+    let posts = hdk::query(EntryType::from("post"), 0).unwrap();
+    assert_eq!(posts.len(), 2);
+    let header = hdk::get_header(posts[0].clone()).unwrap().unwrap();
+    let headers = hdk::query_headers(EntryType::from("post"), 1).unwrap();
+    assert_eq!(headers, vec![header]);
+}
+
+#[test]
+fn can_read_mock_globals() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| host.set_global("app_name", "my_app"));
+    let result = hdk::mock::call(app_name, json!({})).unwrap();
+    assert_eq!(result, r#""my_app""#);
+    hdk::mock::with_host(|host| assert_eq!(host.debug_log(), &["reading app name".to_string()]));
+}
+
+#[test]
+fn refreshed_globals_replace_the_cached_ones() {
+    hdk::mock::reset();
+    let first = hdk::try_globals().unwrap();
+    hdk::mock::with_host(|host| host.set_global("app_name", "my_app"));
+    assert_eq!(hdk::try_globals().unwrap().app_name, first.app_name);
+
+    let refreshed = hdk::refresh_globals().unwrap();
+    assert_eq!(refreshed.app_name, "my_app");
+    assert_eq!(hdk::try_globals().unwrap().app_name, "my_app");
+    assert_ne!(first.app_name, "my_app");
+}
+
+#[test]
+fn reports_the_invalid_global_field() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| host.set_global("app_agent_key_hash", 42));
+    match hdk::try_globals() {
+        Err(RibosomeError::RibosomeFailed(message)) => {
+            assert!(message.starts_with("AppGlobals field `app_agent_key_hash` is invalid"), "{}", message)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    hdk::mock::with_host(|host| host.remove_global("app_name"));
+    match hdk::refresh_globals() {
+        Err(RibosomeError::RibosomeFailed(message)) => {
+            assert_eq!(message, "AppGlobals field `app_name` is missing")
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[derive(Debug, Deserialize, PartialEq)]
+#[serde(rename_all = "PascalCase")]
+struct Profile {
+    first_name: String,
+    last_name: String,
+}
+
+#[test]
+fn can_parse_a_json_agent_identity() {
+    hdk::mock::reset();
+    let identity = json!({ "FirstName": "Alice", "LastName": "Smith" }).to_string();
+    hdk::mock::with_host(|host| host.set_global("app_agent_id_str", &identity));
+    hdk::refresh_globals().unwrap();
+
+    let profile: Profile = hdk::agent_id_as().unwrap();
+    assert_eq!(
+        profile,
+        Profile {
+            first_name: "Alice".to_string(),
+            last_name: "Smith".to_string(),
+        }
+    );
+    let agent_id = hdk::agent_id().unwrap();
+    assert_eq!(agent_id.identity(), identity);
+    assert_eq!(agent_id.parse::<Profile>().unwrap(), profile);
+    assert_eq!(agent_id.property("FirstName"), Some(json!("Alice")));
+    assert_eq!(agent_id.property("Email"), None);
+}
+
+#[test]
+fn reports_a_plain_agent_identity_as_unparsable() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| host.set_global("app_agent_id_str", "alice"));
+    hdk::refresh_globals().unwrap();
+
+    match hdk::agent_id_as::<Profile>() {
+        Err(RibosomeError::RibosomeFailed(message)) => {
+            assert!(message.starts_with("agent identity is not the expected JSON: "), "{}", message)
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    let agent_id = hdk::agent_id().unwrap();
+    assert_eq!(agent_id.identity(), "alice");
+    assert!(agent_id.parse::<Profile>().is_err());
+    assert_eq!(agent_id.property("FirstName"), None);
+
+    hdk::mock::with_host(|host| host.set_global("app_agent_id_str", r#"{"FirstName":"Alice"}"#));
+    hdk::refresh_globals().unwrap();
+    assert!(hdk::agent_id_as::<Profile>().is_err());
+    assert_eq!(hdk::agent_id().unwrap().property("FirstName"), Some(json!("Alice")));
+}
+
+#[test]
+fn call_globals_are_read_for_each_call() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| host.set_global("timestamp", 1000));
+    let result = hdk::mock::call(call_details, json!({})).unwrap();
+    let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(result["zome"], "mock_zome");
+    assert_eq!(result["timestamp"], 1000);
+
+    hdk::mock::with_host(|host| {
+        host.set_global("timestamp", 2000);
+        host.set_global("capability_name", "admin");
+    });
+    let result = hdk::mock::call(call_details, json!({})).unwrap();
+    let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert_eq!(result["timestamp"], 2000);
+    assert_eq!(result["capability"], "admin");
+}
+
+#[test]
+fn reports_panics_as_errors() {
+    hdk::mock::reset();
+    let result = hdk::mock::call(get_post, json!({ "post_hash": "QmUnknown" })).unwrap();
+    let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+    let error = result["error"].as_str().unwrap();
+    assert!(error.starts_with("Zome function panicked at tests/mock_test.rs:"));
+    assert!(error.ends_with(": post should exist"));
+
+    let report = hdk::mock::with_host(|host| host.debug_log()[0].clone());
+    let report: serde_json::Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["level"], "error");
+    assert_eq!(report["function"], "get_post");
+    assert_eq!(report["message"], "panicked: post should exist");
+    assert_eq!(hdk::zome_call::current_function(), None);
+}
+
+#[test]
+fn reports_panics_while_the_mock_host_is_borrowed() {
+    hdk::mock::reset();
+    let result = hdk::mock::call(check_chain, json!({})).unwrap();
+    let result: serde_json::Value = serde_json::from_str(&result).unwrap();
+    assert!(result["error"].as_str().unwrap().ends_with(": chain should not be empty"));
+    // The report could not reach the borrowed mock host
+    hdk::mock::with_host(|host| assert!(host.debug_log().is_empty()));
+}
+
+#[test]
+fn panics_outside_zome_functions_are_not_reported() {
+    hdk::mock::reset();
+    hdk::zome_call::install_panic_hook();
+    let result = std::panic::catch_unwind(|| {
+        hdk::mock::with_host(|host| assert!(!host.chain().is_empty(), "chain should not be empty"))
+    });
+    assert!(result.is_err());
+    hdk::mock::with_host(|host| assert!(host.debug_log().is_empty()));
+}
+
+#[test]
+fn reports_mismatched_api_version() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| host.set_global("api_version", hdk::VERSION + 1));
+    match hdk::check_api_version() {
+        Err(RibosomeError::ApiVersionMismatch { hdk, host }) => assert_eq!(hdk + 1, host),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    let result = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    assert!(result.starts_with(r#"{"error":"#), "result = {}", result);
+    hdk::mock::with_host(|host| assert!(host.chain().is_empty()));
+}
+
+#[test]
+fn api_version_is_checked_once() {
+    hdk::mock::reset();
+    hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    hdk::mock::call(create_post, json!({ "content": "world" })).unwrap();
+    assert_eq!(hdk::mock::calls_to("hc_init_globals").len(), 1);
+}
+
+#[test]
+fn api_version_is_checked_again_after_a_failed_read() {
+    hdk::mock::reset();
+    hdk::mock::expect("hc_init_globals").returns_err("globals are unavailable");
+    assert!(hdk::check_api_version().is_ok());
+
+    hdk::mock::with_host(|host| host.set_global("api_version", hdk::VERSION + 1));
+    match hdk::check_api_version() {
+        Err(RibosomeError::ApiVersionMismatch { hdk, host }) => assert_eq!(hdk + 1, host),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn early_returns_end_the_zome_call() {
+    hdk::mock::reset();
+    hdk::mock::call(create_post, json!({ "content": 42 })).unwrap_err();
+    assert_eq!(hdk::zome_call::current_function(), None);
+
+    hdk::mock::with_host(|host| host.set_global("api_version", hdk::VERSION + 1));
+    hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    assert_eq!(hdk::zome_call::current_function(), None);
+}
+
+#[test]
+fn invalid_globals_do_not_fail_functions_not_using_them() {
+    hdk::mock::reset();
+    hdk::mock::with_host(|host| host.set_global("app_name", 42));
+    let result = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    assert!(result.starts_with("\"Qm"), "result = {}", result);
+}
+
+#[test]
+fn can_script_host_results() {
+    hdk::mock::reset();
+    let post_hash = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    let post_hash: HashString = serde_json::from_str(&post_hash).unwrap();
+
+    hdk::mock::expect_get_entry().not_found();
+    let result = hdk::mock::call(check_get_entry, json!({ "entry_hash": post_hash })).unwrap();
+    assert_eq!(result, r#"{"got back no entry":true}"#);
+    let result = hdk::mock::call(check_get_entry, json!({ "entry_hash": post_hash })).unwrap();
+    assert_eq!(result, r#"{"entry":"{\"content\":\"hello\"}"}"#);
+
+    let error = hdk::ValidationError::new("custom", "chain is full");
+    hdk::mock::expect_commit().fails_validation(vec![error.clone()]);
+    match hdk::commit_entry("post", json!({ "content": "again" })) {
+        Err(RibosomeError::ValidationFailed(errors)) => assert_eq!(errors, vec![error]),
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    hdk::mock::expect_send().times_out();
+    match hdk::send(HashString::from("QmOtherAgent"), json!({ "ping": true })) {
+        Err(RibosomeError::RibosomeFailed(error)) => assert_eq!(error, hdk::mock::TIMEOUT_ERROR),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    hdk::mock::verify();
+}
+
+#[test]
+fn records_host_calls() {
+    hdk::mock::reset();
+    hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+
+    let commits = hdk::mock::calls_to("hc_commit_entry");
+    assert_eq!(commits.len(), 2);
+    assert_eq!(commits[0].input["entry_type_name"], json!("post"));
+    assert_eq!(commits[1].input["entry_type_name"], json!("agent_anchor"));
+    let links = hdk::mock::calls_to("hc_link_entries");
+    assert_eq!(links[0].input["tag"], json!("authored_posts"));
+    assert_eq!(links[0].output, Ok(serde_json::Value::Null));
+}
+
+#[test]
+#[should_panic(expected = "expected calls to hc_get_links were not made")]
+fn reports_unmet_expectations() {
+    hdk::mock::reset();
+    hdk::mock::expect_get_links().times(2).returns_err("network error");
+    let _ = hdk::get_links(HashString::from("QmBase"), "posts");
+    hdk::mock::verify();
+}
+
+#[derive(Serialize)]
+struct CreatePostInput {
+    content: String,
+}
+
+#[test]
+fn can_call_with_typed_input() {
+    hdk::mock::reset();
+    let input = CreatePostInput {
+        content: "typed".to_string(),
+    };
+    let post_hash: HashString = hdk::testing::call(create_post, &input).unwrap();
+    assert_eq!(hdk::get_entry(post_hash).unwrap(), Some(r#"{"content":"typed"}"#.to_string()));
+
+    let result: Result<u32, String> = hdk::testing::call(create_post, &input);
+    assert!(result.unwrap_err().starts_with("could not decode zome function output"));
+}
+
+#[test]
+fn can_get_bridges() {
+    hdk::mock::reset();
+    assert_eq!(hdk::get_bridges().unwrap(), vec![]);
+
+    let bridge = Bridge {
+        name: "accounts".to_string(),
+        side: BridgeSide::From,
+        dna_hash: HashString::from("QmAccounts"),
+    };
+    hdk::mock::with_host(|host| host.add_bridge(bridge.clone()));
+    assert_eq!(hdk::get_bridges().unwrap(), vec![bridge]);
+
+    hdk::mock::expect("hc_get_bridges").returns_err("bridges are unavailable");
+    match hdk::get_bridges() {
+        Err(RibosomeError::RibosomeFailed(message)) => assert_eq!(message, "bridges are unavailable"),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn bridge_call_serializes_its_arguments() {
+    hdk::mock::reset();
+    hdk::mock::expect_bridge_call().returns(json!({ "result": r#"{"balance":10}"# }));
+    let result = hdk::bridge_call("accounts", "ledger", "balance", json!({ "account": "alice" }));
+    assert_eq!(result.unwrap(), json!({ "balance": 10 }));
+
+    let call = &hdk::mock::calls_to("hc_bridge_call")[0];
+    assert_eq!(
+        call.input,
+        json!({
+            "bridge_name": "accounts",
+            "zome_name": "ledger",
+            "function_name": "balance",
+            "arguments": r#"{"account":"alice"}"#,
+        })
+    );
+    hdk::mock::verify();
+}
+
+#[test]
+fn bridge_call_reports_host_and_output_errors() {
+    hdk::mock::reset();
+    match hdk::bridge_call("accounts", "ledger", "balance", json!({})) {
+        Err(RibosomeError::RibosomeFailed(message)) => {
+            assert_eq!(message, "bridge accounts can not be called through the mock host")
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+
+    hdk::mock::expect_bridge_call().returns(json!({ "result": "not json" }));
+    match hdk::bridge_call("accounts", "ledger", "balance", json!({})) {
+        Err(RibosomeError::RibosomeFailed(message)) => assert!(message.starts_with("expected ident")),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn can_get_entries_of_a_type() {
+    hdk::mock::reset();
+    let post_hash = hdk::commit_entry("post", json!({ "content": "hello" })).unwrap();
+    let deletion_hash = hdk::commit_entry("%deletion", json!({ "deleted": post_hash })).unwrap();
+
+    let post = hdk::get_entry_of_type(post_hash.clone(), EntryType::from("post")).unwrap();
+    assert_eq!(post, Some(r#"{"content":"hello"}"#.to_string()));
+    assert_eq!(hdk::get_entry_of_type(post_hash, SystemEntryType::Deletion.into()).unwrap(), None);
+
+    let deletion = hdk::get_entry_of_type(deletion_hash.clone(), SystemEntryType::Deletion.into());
+    assert!(deletion.unwrap().is_some());
+    assert_eq!(hdk::get_entry_of_type(deletion_hash, EntryType::from("post")).unwrap(), None);
+
+    // Entries of other agents have no header on the local chain
+    let other_hash = hdk::mock::with_host(|host| host.put_entry(EntryType::from("post"), "{}"));
+    assert_eq!(hdk::get_entry_of_type(other_hash, EntryType::from("post")).unwrap(), None);
+}
+
+#[test]
+fn get_links_and_load_gets_every_page() {
+    hdk::mock::reset();
+    hdk::mock::expect_get_links().returns(link_page(&["first"], Some("page-2")));
+    hdk::mock::expect_get_links().returns(link_page(&["second"], None));
+
+    let posts: Vec<Post> = hdk::get_links_and_load(HashString::from("QmBase"), "posts")
+        .unwrap()
+        .into_iter()
+        .map(Result::unwrap)
+        .collect();
+    let contents: Vec<String> = posts.into_iter().map(|post| post.content).collect();
+    assert_eq!(contents, vec!["first", "second"]);
+
+    let calls = hdk::mock::calls_to("hc_get_links");
+    assert_eq!(calls[1].input["options"]["page_token"], "page-2");
+    assert_eq!(calls[1].input["options"]["load_entries"], true);
+    hdk::mock::verify();
+}
+
+#[test]
+fn can_commit_and_get_several_entries() {
+    hdk::mock::reset();
+    let results = hdk::commit_entries(vec![
+        ("post", json!({ "content": "first" })),
+        ("post", json!({ "content": "second" })),
+    ]).unwrap();
+    let addresses: Vec<HashString> = results.into_iter().map(Result::unwrap).collect();
+    assert_eq!(hdk::mock::calls_to("hc_commit_entries").len(), 1);
+
+    let mut lookups = addresses.clone();
+    lookups.insert(1, HashString::from("QmUnknown"));
+    let entries = hdk::get_entries(lookups).unwrap();
+    assert_eq!(
+        entries,
+        vec![
+            Some(r#"{"content":"first"}"#.to_string()),
+            None,
+            Some(r#"{"content":"second"}"#.to_string()),
+        ]
+    );
+}
+
+#[test]
+fn commit_entries_reports_each_validation_failure() {
+    hdk::mock::reset();
+    let error = hdk::ValidationError::new("required", "content is empty");
+    hdk::mock::expect("hc_commit_entries").returns(json!({
+        "results": [
+            { "address": "QmFirst", "validation_failure": "" },
+            { "address": "", "validation_failure": json!([error]).to_string() },
+        ]
+    }));
+    let results = hdk::commit_entries(vec![
+        ("post", json!({ "content": "first" })),
+        ("post", json!({ "content": "" })),
+    ]).unwrap();
+    assert_eq!(results[0].as_ref().unwrap(), &HashString::from("QmFirst"));
+    match results[1] {
+        Err(RibosomeError::ValidationFailed(ref errors)) => assert_eq!(errors, &vec![error]),
+        ref result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn commit_entries_is_not_atomic() {
+    hdk::mock::reset();
+    let result = hdk::commit_entries(vec![
+        ("post", json!({ "content": "first" })),
+        ("%unknown", json!({})),
+        ("post", json!({ "content": "third" })),
+    ]);
+    match result {
+        Err(RibosomeError::RibosomeFailed(message)) => {
+            assert_eq!(message, "unknown system entry type: %unknown")
+        }
+        result => panic!("unexpected result: {:?}", result),
+    }
+    // The entries before the failing one stay committed
+    hdk::mock::with_host(|host| {
+        assert_eq!(host.chain().len(), 1);
+        assert_eq!(host.chain()[0].entry, r#"{"content":"first"}"#);
+    });
+}
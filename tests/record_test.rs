@@ -0,0 +1,127 @@
+#![cfg(all(feature = "mock", feature = "record"))]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::mock::{replay, Recording};
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+
+zome_functions! {
+    create_post: |content: String| {
+        let post_hash = hdk::commit_entry("post", json!({ "content": content })).unwrap();
+        match hdk::get_entry(post_hash.clone()) {
+            Ok(Some(_)) => json!({ "address": post_hash }),
+            _ => json!({ "error": "post was not stored" }),
+        }
+    }
+
+    read_post: |address: HashString| {
+        hdk::get_entry(address).unwrap()
+    }
+}
+
+genesis! {
+    || {
+        hdk::commit_entry("handle", json!({ "name": "alex" }))
+            .map(|_| ())
+            .map_err(|err| err.to_string())
+    }
+}
+
+// Records a call of `create_post` in the debug log of the mock host
+fn record_create_post(content: &str) -> (String, String) {
+    hdk::mock::reset();
+    let output = hdk::mock::call(create_post, json!({ "content": content })).unwrap();
+    let log = hdk::mock::with_host(|host| host.debug_log().join("\n"));
+    (output, log)
+}
+
+#[test]
+fn records_zome_and_host_calls() {
+    let (output, log) = record_create_post("hello");
+    let recording = Recording::from_log(&log).unwrap();
+    assert_eq!(recording.calls.len(), 1);
+    let call = &recording.calls[0];
+    assert_eq!(call.function, "create_post");
+    assert_eq!(call.input, json!({ "content": "hello" }));
+    let functions: Vec<&str> = call
+        .host_calls
+        .iter()
+        .map(|host_call| host_call.function.as_str())
+        .collect();
+    assert_eq!(functions, vec!["hc_init_globals", "hc_commit_entry", "hc_get_entry"]);
+    assert_eq!(call.output, Some(serde_json::from_str(&output).unwrap()));
+}
+
+#[test]
+fn replays_recorded_call() {
+    let (output, log) = record_create_post("hello");
+    // Conductor logs prefix the messages of the zome
+    let log: String = log.lines().map(|line| format!("debug/dna: {}\n", line)).collect();
+    let recording = Recording::from_log(&log).unwrap();
+
+    assert_eq!(replay(&recording.calls[0], create_post), Ok(output));
+    // The replay ran against an empty mock host, answered from the recording only
+    hdk::mock::with_host(|host| assert!(host.chain().is_empty()));
+}
+
+#[test]
+fn replays_recorded_host_failures() {
+    let (_, log) = record_create_post("hello");
+    let mut recording = Recording::from_log(&log).unwrap();
+    let call = &mut recording.calls[0];
+    call.host_calls[2].output = Ok(json!({ "status": "NotFound", "entry": "" }));
+    call.output = None;
+    assert_eq!(
+        replay(call, create_post),
+        Ok(r#"{"error":"post was not stored"}"#.to_string())
+    );
+}
+
+#[test]
+fn reports_diverging_replays() {
+    let (_, log) = record_create_post("hello");
+    let recording = Recording::from_log(&log).unwrap();
+    let mut call = recording.calls[0].clone();
+    call.input = json!({ "content": "bye" });
+    let divergence = replay(&call, create_post).unwrap_err();
+    assert!(
+        divergence.starts_with("host call 2 to hc_commit_entry was made with"),
+        "{}",
+        divergence
+    );
+
+    // Same input to another host function: only the name tells the calls apart
+    let mut call = recording.calls[0].clone();
+    call.host_calls[2].function = "hc_get_header".to_string();
+    let divergence = replay(&call, create_post).unwrap_err();
+    assert_eq!(divergence, "host call 3 to hc_get_entry was recorded to hc_get_header");
+
+    // read_post refuses the recorded input before calling the host
+    let divergence = replay(&recording.calls[0], read_post).unwrap_err();
+    assert_eq!(divergence, "2 recorded host calls were not made");
+}
+
+#[test]
+fn records_and_replays_callbacks_with_zome_functions() {
+    hdk::mock::reset();
+    assert_eq!(hdk::mock::call(genesis, json!({})), Ok(String::new()));
+    let output = hdk::mock::call(create_post, json!({ "content": "hello" })).unwrap();
+    let log = hdk::mock::with_host(|host| host.debug_log().join("\n"));
+
+    let recording = Recording::from_log(&log).unwrap();
+    let functions: Vec<&str> = recording.calls.iter().map(|call| call.function.as_str()).collect();
+    assert_eq!(functions, vec!["genesis", "create_post"]);
+    assert_eq!(recording.calls[0].output, Some(serde_json::Value::Null));
+
+    assert_eq!(replay(&recording.calls[0], genesis), Ok(String::new()));
+    // genesis checked the API version, so create_post did not read the globals
+    assert_eq!(recording.calls[1].host_calls[0].function, "hc_commit_entry");
+    assert_eq!(replay(&recording.calls[1], create_post), Ok(output));
+}
@@ -0,0 +1,121 @@
+extern crate hdk;
+
+use hdk::testing::ZomeManifest;
+use hdk::zome_call::CALLBACK_MARKER_MAGIC;
+
+fn leb128(mut value: usize, bytes: &mut Vec<u8>) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+fn section(id: u8, content: Vec<u8>, wasm: &mut Vec<u8>) {
+    wasm.push(id);
+    leb128(content.len(), wasm);
+    wasm.extend(content);
+}
+
+// Builds a WASM module exporting the given functions, with every function in the table
+// from index 1, and a data segment holding the callback markers of `callbacks`
+fn wasm_with_exports(exports: &[(&str, u8)], callbacks: &[&str]) -> Vec<u8> {
+    wasm_with_data(exports, callbacks, &[])
+}
+
+// Same as `wasm_with_exports`, with `data` at the end of the data segment
+fn wasm_with_data(exports: &[(&str, u8)], callbacks: &[&str], data: &[u8]) -> Vec<u8> {
+    let mut export_section = vec![exports.len() as u8];
+    for (index, &(name, kind)) in exports.iter().enumerate() {
+        export_section.push(name.len() as u8);
+        export_section.extend_from_slice(name.as_bytes());
+        export_section.push(kind);
+        export_section.push(index as u8);
+    }
+    // One segment at offset 1, as `i32.const 1` followed by `end`
+    let mut element_section = vec![1, 0, 0x41, 1, 0x0b, exports.len() as u8];
+    element_section.extend((0..exports.len()).map(|index| index as u8));
+    // One segment at offset 1024, starting with unrelated data
+    let mut segment = b"posts".to_vec();
+    for (index, &(name, _)) in exports.iter().enumerate() {
+        if callbacks.contains(&name) {
+            segment.extend_from_slice(&CALLBACK_MARKER_MAGIC);
+            segment.extend_from_slice(&[index as u8 + 1, 0, 0, 0]);
+        }
+    }
+    segment.extend_from_slice(data);
+    let mut data_section = vec![1, 0, 0x41, 0x80, 0x08, 0x0b];
+    leb128(segment.len(), &mut data_section);
+    data_section.extend(segment);
+
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    section(7, export_section, &mut wasm);
+    section(9, element_section, &mut wasm);
+    section(11, data_section, &mut wasm);
+    wasm
+}
+
+#[test]
+fn can_read_zome_manifest() {
+    let wasm = wasm_with_exports(
+        &[
+            ("memory", 2),
+            ("create_post", 0),
+            ("validate_post", 0),
+            ("__hdk_version", 0),
+            ("genesis", 0),
+            ("list_posts", 0),
+        ],
+        &["validate_post", "genesis"],
+    );
+    let manifest = ZomeManifest::from_wasm(&wasm).unwrap();
+    assert_eq!(manifest.function_names(), vec!["create_post", "list_posts"]);
+    assert_eq!(manifest.callbacks, vec!["validate_post", "genesis"]);
+}
+
+#[test]
+fn tells_callbacks_by_their_marker_not_their_name() {
+    let wasm = wasm_with_exports(
+        &[("validate_handle", 0), ("check_post", 0)],
+        &["check_post"],
+    );
+    let manifest = ZomeManifest::from_wasm(&wasm).unwrap();
+    assert_eq!(manifest.function_names(), vec!["validate_handle"]);
+    assert_eq!(manifest.callbacks, vec!["check_post"]);
+}
+
+#[test]
+fn refuses_invalid_wasm() {
+    assert!(ZomeManifest::from_wasm(b"not wasm").is_err());
+    let mut truncated = wasm_with_exports(&[("create_post", 0)], &[]);
+    truncated.pop();
+    assert!(ZomeManifest::from_wasm(&truncated).is_err());
+}
+
+#[test]
+fn skips_marker_magic_not_pointing_to_a_function() {
+    // The magic bytes in other data, followed by an index outside of the table, then truncated
+    let mut data = CALLBACK_MARKER_MAGIC.to_vec();
+    data.extend_from_slice(&[99, 0, 0, 0]);
+    data.extend_from_slice(&CALLBACK_MARKER_MAGIC);
+    data.extend_from_slice(&[1, 0]);
+    let wasm = wasm_with_data(&[("create_post", 0), ("genesis", 0)], &["genesis"], &data);
+    let manifest = ZomeManifest::from_wasm(&wasm).unwrap();
+    assert_eq!(manifest.function_names(), vec!["create_post"]);
+    assert_eq!(manifest.callbacks, vec!["genesis"]);
+}
+
+#[test]
+fn refuses_elements_outside_of_the_table() {
+    // Two elements at offset `i32.const -1`, the last table index
+    let mut wasm = b"\0asm\x01\0\0\0".to_vec();
+    section(9, vec![1, 0, 0x41, 0x7f, 0x0b, 2, 0, 0], &mut wasm);
+    assert_eq!(
+        ZomeManifest::from_wasm(&wasm),
+        Err("element segment outside of the WASM table".to_string())
+    );
+}
@@ -0,0 +1,230 @@
+#![cfg(feature = "mock")]
+
+#[macro_use]
+extern crate hdk;
+extern crate holochain_wasm_utils;
+extern crate serde;
+#[macro_use]
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+use hdk::{EntryType, RibosomeError, ValidationContext, ValidationError};
+use holochain_wasm_utils::holochain_core_types::hash::HashString;
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize)]
+struct Post {
+    content: String,
+}
+
+validations! {
+    [ENTRY] validate_post {
+        [hdk::ValidationPackage::Entry]
+        |post: Post, _ctx: hdk::ValidationData| -> ValidationError {
+            if post.content.is_empty() {
+                Err(ValidationError::new("required", "content is empty").with_field("content"))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    [UPDATE] validate_update_post {
+        [hdk::ValidationPackage::Entry]
+        |old_post: Post, new_post: Post, _ctx: hdk::ValidationData| {
+            if new_post.content.starts_with(&old_post.content) {
+                Ok(())
+            } else {
+                Err(format!("{} does not extend {}", new_post.content, old_post.content))
+            }
+        }
+    }
+
+    [DELETE] validate_delete_post {
+        [hdk::ValidationPackage::Entry]
+        |old_post: Post, ctx: hdk::ValidationData| {
+            if ctx.sources.is_empty() {
+                Err(format!("{} has no author", old_post.content))
+            } else {
+                Ok(())
+            }
+        }
+    }
+
+    [LINK] validate_link_post {
+        [hdk::ValidationPackage::Entry]
+        |base: HashString, target: HashString, tag: String, action: hdk::LinkAction, _ctx: hdk::ValidationData| {
+            match action {
+                hdk::LinkAction::Add if tag == "comments" && base != target => Ok(()),
+                hdk::LinkAction::Add => Err(format!("can not link {} to {} as {}", base, target, tag)),
+                hdk::LinkAction::Delete => Err("comments can not be unlinked".to_string()),
+            }
+        }
+    }
+
+    [ENTRY] validate_anchor {
+        [hdk::ValidationPackage::Entry]
+        |_anchor: String, _ctx: hdk::ValidationData| {
+            Ok(())
+        }
+    }
+
+    [ENTRY] validate_comment {
+        [hdk::ValidationPackage::Entry]
+        |comment: String, _ctx: hdk::ValidationData| {
+            assert!(!comment.is_empty(), "comment should not be empty");
+            Ok(())
+        }
+    }
+}
+
+fn ctx(sources: &[&str]) -> Value {
+    json!({
+        "sources": sources,
+        "source_chain_entries": null,
+        "source_chain_headers": null,
+        "custom": null,
+        "lifecycle": "Chain",
+        "action": "Commit",
+    })
+}
+
+#[test]
+fn entry_callbacks_receive_the_entry() {
+    hdk::mock::reset();
+    let post = json!({ "content": "hello" });
+    assert_eq!(hdk::mock::validate(validate_post, json!({ "entry": post, "ctx": ctx(&[]) })), Ok(()));
+
+    let empty_post = json!({ "content": "" });
+    assert_eq!(
+        hdk::mock::validate(validate_post, json!({ "entry": empty_post, "ctx": ctx(&[]) })),
+        Err(vec![ValidationError::new("required", "content is empty").with_field("content")])
+    );
+}
+
+#[test]
+fn callbacks_may_only_accept() {
+    hdk::mock::reset();
+    let input = json!({ "entry": "all_posts", "ctx": ctx(&[]) });
+    assert_eq!(hdk::mock::validate(validate_anchor, input), Ok(()));
+}
+
+#[test]
+fn callbacks_reject_when_they_panic() {
+    hdk::mock::reset();
+    let input = json!({ "entry": "", "ctx": ctx(&[]) });
+    let errors = hdk::mock::validate(validate_comment, input).unwrap_err();
+    assert_eq!(errors[0].code, "custom");
+    assert!(errors[0].message.starts_with("Zome function panicked at tests/validations_test.rs:"));
+    assert!(errors[0].message.ends_with(": comment should not be empty"));
+
+    let report = hdk::mock::with_host(|host| host.debug_log()[0].clone());
+    let report: Value = serde_json::from_str(&report).unwrap();
+    assert_eq!(report["message"], "panicked: comment should not be empty");
+}
+
+#[test]
+fn update_callbacks_receive_the_old_and_new_entries() {
+    hdk::mock::reset();
+    let input = json!({
+        "old_entry": { "content": "hello" },
+        "new_entry": { "content": "hello world" },
+        "ctx": ctx(&[]),
+    });
+    assert_eq!(hdk::mock::validate(validate_update_post, input), Ok(()));
+
+    let input = json!({
+        "old_entry": { "content": "hello" },
+        "new_entry": { "content": "bye" },
+        "ctx": ctx(&[]),
+    });
+    assert_eq!(
+        hdk::mock::validate(validate_update_post, input),
+        Err(vec![ValidationError::new("custom", "bye does not extend hello")])
+    );
+}
+
+#[test]
+fn delete_callbacks_receive_the_old_entry_and_context() {
+    hdk::mock::reset();
+    let input = json!({ "old_entry": { "content": "hello" }, "ctx": ctx(&["QmAlice"]) });
+    assert_eq!(hdk::mock::validate(validate_delete_post, input), Ok(()));
+
+    let input = json!({ "old_entry": { "content": "hello" }, "ctx": ctx(&[]) });
+    assert_eq!(
+        hdk::mock::validate(validate_delete_post, input),
+        Err(vec![ValidationError::new("custom", "hello has no author")])
+    );
+}
+
+#[test]
+fn link_callbacks_receive_the_link_and_action() {
+    hdk::mock::reset();
+    let link = |tag: &str, action: &str| {
+        json!({
+            "base": "QmPost",
+            "target": "QmComment",
+            "tag": tag,
+            "action": action,
+            "ctx": ctx(&[]),
+        })
+    };
+    assert_eq!(hdk::mock::validate(validate_link_post, link("comments", "Add")), Ok(()));
+    assert_eq!(
+        hdk::mock::validate(validate_link_post, link("likes", "Add")),
+        Err(vec![ValidationError::new("custom", "can not link QmPost to QmComment as likes")])
+    );
+    assert_eq!(
+        hdk::mock::validate(validate_link_post, link("comments", "Delete")),
+        Err(vec![ValidationError::new("custom", "comments can not be unlinked")])
+    );
+}
+
+fn put_member_list(content: Value) -> HashString {
+    hdk::mock::with_host(|host| host.put_entry(EntryType::from("members"), &content.to_string()))
+}
+
+fn validation_data(sources: &[&str]) -> hdk::ValidationData {
+    serde_json::from_value(ctx(sources)).unwrap()
+}
+
+#[test]
+fn author_in_reads_lists_of_addresses() {
+    hdk::mock::reset();
+    let list = put_member_list(json!(["QmAlice", "QmBob"]));
+    assert_eq!(validation_data(&["QmBob"]).author_in(list.clone()).unwrap(), true);
+    assert_eq!(validation_data(&["QmCarol", "QmAlice"]).author_in(list).unwrap(), false);
+}
+
+#[test]
+fn author_in_reads_objects_with_members() {
+    hdk::mock::reset();
+    let list = put_member_list(json!({ "name": "admins", "members": ["QmAlice"] }));
+    assert_eq!(validation_data(&["QmAlice"]).author_in(list.clone()).unwrap(), true);
+    assert_eq!(validation_data(&["QmBob"]).author_in(list).unwrap(), false);
+}
+
+#[test]
+fn author_in_is_false_without_author() {
+    hdk::mock::reset();
+    let list = put_member_list(json!(["QmAlice"]));
+    assert_eq!(validation_data(&[]).author_in(list).unwrap(), false);
+}
+
+#[test]
+fn author_in_fails_on_missing_or_invalid_lists() {
+    hdk::mock::reset();
+    match validation_data(&["QmAlice"]).author_in(HashString::from("QmUnknown")) {
+        Err(RibosomeError::HashNotFound) => (),
+        result => panic!("expected HashNotFound, got {:?}", result),
+    }
+
+    let list = put_member_list(json!({ "admins": ["QmAlice"] }));
+    match validation_data(&["QmAlice"]).author_in(list) {
+        Err(RibosomeError::RibosomeFailed(message)) => {
+            assert!(message.starts_with("member list entry is not a list of addresses"))
+        }
+        result => panic!("expected RibosomeFailed, got {:?}", result),
+    }
+}
@@ -1,5 +1,7 @@
 #[macro_use]
 extern crate hdk;
+#[macro_use]
+extern crate hdk_derive;
 extern crate holochain_wasm_utils;
 extern crate serde;
 #[macro_use]
@@ -29,6 +31,11 @@ pub extern "C" fn check_global(encoded_allocation_of_input: u32) -> u32 {
     hdk::debug(&hdk::APP_AGENT_KEY_HASH.to_string());
     hdk::debug(&hdk::APP_AGENT_INITIAL_HASH.to_string());
     hdk::debug(&hdk::APP_AGENT_LATEST_HASH.to_string());
+    hdk::debug(&format!("{:?}", hdk::zome_name()));
+    hdk::debug(&format!("{:?}", hdk::capability_name()));
+    hdk::debug(&format!("{:?}", hdk::call_provenance()));
+    hdk::debug(&format!("{:?}", hdk::call_timestamp()));
+    hdk::debug(hdk::VERSION_STR);
 
     return 0;
 }
@@ -85,12 +92,29 @@ zome_functions! {
         let res = hdk::commit_entry(&entry_type_name, entry_content.unwrap());
         match res {
             Ok(hash_str) => json!({ "address": hash_str }),
-            Err(RibosomeError::ValidationFailed(msg)) => json!({ "validation failed": msg}),
+            Err(RibosomeError::ValidationFailed(errors)) => json!({ "validation failed": errors}),
             Err(RibosomeError::RibosomeFailed(err_str)) => json!({ "error": err_str}),
             Err(_) => unreachable!(),
         }
     }
 
+    check_try_globals: | | {
+        match hdk::try_globals() {
+            Ok(app_globals) => json!({ "app_name": app_globals.app_name }),
+            Err(err) => err.to_json(),
+        }
+    }
+
+    check_agent_id: | | {
+        match hdk::agent_id() {
+            Ok(agent_id) => json!({
+                "identity": agent_id.identity(),
+                "key_hash": agent_id.key_hash(),
+            }),
+            Err(err) => err.to_json(),
+        }
+    }
+
     check_get_entry: |entry_hash: HashString| {
         let res = hdk::get_entry(entry_hash);
         match res {
@@ -136,3 +160,70 @@ validations! {
         }
     }
 }
+
+validations! {
+    [UPDATE] validate_update_testEntryType {
+        [hdk::ValidationPackage::Entry]
+        |old_entry: TestEntryType, new_entry: TestEntryType, _ctx: hdk::ValidationData| -> hdk::ValidationError {
+            (old_entry.stuff != new_entry.stuff).ok_or_else(|| {
+                hdk::ValidationError::new("unchanged", "update must change content")
+                    .with_field("stuff")
+            })
+        }
+    }
+
+    [DELETE] validate_delete_testEntryType {
+        [hdk::ValidationPackage::Entry]
+        |old_entry: TestEntryType, _ctx: hdk::ValidationData| {
+            (old_entry.stuff != "PERMANENT")
+                .ok_or_else(|| "PERMANENT content can not be deleted".to_string())
+        }
+    }
+
+    [LINK] validate_link_testEntryType {
+        [hdk::ValidationPackage::Entry]
+        |_base: HashString, _target: HashString, tag: String, _action: hdk::LinkAction, ctx: hdk::ValidationData| {
+            if tag == "FAIL" {
+                Err("FAIL tag is not allowed".to_string())
+            } else if tag == "admin_of" && !ctx.is_signed_by(&hdk::APP_AGENT_KEY_HASH) {
+                Err("admin_of links must be signed by this agent".to_string())
+            } else {
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Validate)]
+struct TestPostType {
+    #[validate(non_empty, length(max = 280))]
+    content: String,
+    #[validate(regex = "^[a-z]+$")]
+    author: String,
+    #[validate(range(min = 1, max = 5))]
+    rating: u8,
+}
+
+validations! {
+    [ENTRY] validate_testPostType {
+        [hdk::ValidationPackage::Entry]
+        |post: TestPostType, _ctx: hdk::ValidationData| {
+            (post.content != "FAIL")
+                .ok_or_else(|| "FAIL content is not allowed".to_string())
+        }
+    }
+}
+
+genesis! {
+    || {
+        hdk::debug("genesis")
+            .map_err(|err| format!("could not log genesis: {:?}", err))
+    }
+}
+
+bridge_genesis! {
+    |side: hdk::BridgeSide, dna_hash: HashString| {
+        hdk::debug(&format!("bridge genesis: {:?} {}", side, dna_hash))
+            .map_err(|err| err.to_string())
+    }
+}